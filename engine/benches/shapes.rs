@@ -0,0 +1,85 @@
+//! Shape iteration: collecting into a `Vec` (what every call used to do)
+//! against walking the lazy iterators directly.
+//!
+//! Run with `cargo bench --bench shapes`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use engine::core::geom::shapes;
+use engine::core::geom::{Delta, Direction};
+
+const ITERS: u32 = 200_000;
+
+/// Average time of one call to `f`, after a short warm up
+fn time<T>(mut f: impl FnMut() -> T) -> Duration {
+    for _ in 0..ITERS / 10 {
+        black_box(f());
+    }
+
+    let start = Instant::now();
+    for _ in 0..ITERS {
+        black_box(f());
+    }
+    start.elapsed() / ITERS
+}
+
+fn compare(name: &str, allocated: impl FnMut() -> i32, lazy: impl FnMut() -> i32) {
+    let (allocated, lazy) = (time(allocated), time(lazy));
+    let gain = allocated.as_secs_f64() / lazy.as_secs_f64();
+
+    println!("{:<16} vec {:>9.1?}   iter {:>9.1?}   {:>5.1}x", name, allocated, lazy, gain);
+}
+
+/// Something cheap to do with every tile, placed around an origin the
+/// optimizer can't see so nothing is folded away
+fn checksum(origin: Delta, shape: impl IntoIterator<Item = Delta>) -> i32 {
+    shape.into_iter().map(|d| {
+        let d = d.add(origin);
+        d.dx() as i32 * 31 + d.dy() as i32
+    }).sum()
+}
+
+fn main() {
+    let radius = black_box(8);
+    let origin = black_box(Delta::new(3, 5));
+    let centers: Vec<Delta> = (0..64).map(|i| black_box(Delta::new(i % 8, i / 8))).collect();
+
+    compare(
+        "disk(8)",
+        || checksum(origin, shapes::disk(radius).collect::<Vec<_>>()),
+        || checksum(origin, shapes::disk(radius)),
+    );
+    compare(
+        "ring(8)",
+        || checksum(origin, shapes::ring(radius).collect::<Vec<_>>()),
+        || checksum(origin, shapes::ring(radius)),
+    );
+    compare(
+        "spiral(8)",
+        || checksum(origin, shapes::spiral(radius).collect::<Vec<_>>()),
+        || checksum(origin, shapes::spiral(radius)),
+    );
+    compare(
+        "line(8)",
+        || checksum(origin, shapes::line(radius, Direction::UpLeft).collect::<Vec<_>>()),
+        || checksum(origin, shapes::line(radius, Direction::UpLeft)),
+    );
+    compare(
+        "cone(8)",
+        || checksum(origin, shapes::cone(radius, Direction::Left).collect::<Vec<_>>()),
+        || checksum(origin, shapes::cone(radius, Direction::Left)),
+    );
+
+    // Vision/AI style loops: the neighbors or a small area around each of many tiles
+    compare(
+        "neighbors x 64",
+        || centers.iter().map(|&c| checksum(c, shapes::neighbors().collect::<Vec<_>>())).sum(),
+        || centers.iter().map(|&c| checksum(c, shapes::neighbors())).sum(),
+    );
+    compare(
+        "disk(2) x 64",
+        || centers.iter().map(|&c| checksum(c, shapes::disk(2).collect::<Vec<_>>())).sum(),
+        || centers.iter().map(|&c| checksum(c, shapes::disk(2))).sum(),
+    );
+}
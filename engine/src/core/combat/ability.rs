@@ -0,0 +1,73 @@
+use super::attack::{AoePattern, AttackRange, TargetFilter};
+use super::cooldown::UsageLimit;
+use super::effect::Effect;
+use crate::core::geom::{Direction, Position};
+use crate::core::unit::UnitId;
+
+/// Index of an ability in its unit class' `abilities`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AbilityId(u16);
+
+impl AbilityId {
+    pub fn new(val: u16) -> Self { AbilityId(val) }
+    pub fn index(&self) -> usize { self.0 as usize }
+}
+
+#[derive(Debug, Clone)]
+pub struct AbilityDefinition {
+    pub name: String,
+    pub targeting: AbilityTargeting,
+    pub area: AoePattern,  // around the targeted tile, as seen from the caster
+    pub affects: TargetFilter,  // which units in the area the effects apply to
+    pub cost: u32,  // gold paid by the owner on each use
+    pub usage: UsageLimit,
+    pub effects: Vec<AbilityEffect>,  // applied in order
+}
+
+/// What an ability must be aimed at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbilityTargeting {
+    /// The caster, e.g. a war cry
+    SelfOnly,
+    Unit { range: AttackRange, filter: TargetFilter },
+    Tile { range: AttackRange },
+    /// One of the six directions from the caster, e.g. a breath
+    Direction,
+}
+
+/// The target a client picked for an ability
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbilityTarget {
+    Myself,
+    Unit(UnitId),
+    Tile(Position),
+    Direction(Direction),
+}
+
+/// One thing an ability does. Abilities never miss.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbilityEffect {
+    /// Flat damage to every affected unit, defense doesn't apply
+    Damage(u32),
+    /// Health back to every affected unit, up to its max health
+    Heal(u32),
+    /// A shield on every affected unit, replacing a weaker one
+    Shield(u32),
+    /// Status effect on every affected unit
+    Status(Effect),
+    /// The caster moves to the targeted tile, which must be free
+    Teleport,
+}
+
+impl AbilityTargeting {
+    /// Whether `target` is the right kind for this targeting
+    pub fn accepts(&self, target: &AbilityTarget) -> bool {
+        matches!(
+            (self, target),
+            (AbilityTargeting::SelfOnly, AbilityTarget::Myself)
+                | (AbilityTargeting::Unit { .. }, AbilityTarget::Unit(_))
+                | (AbilityTargeting::Tile { .. }, AbilityTarget::Tile(_))
+                | (AbilityTargeting::Direction, AbilityTarget::Direction(_))
+        )
+    }
+}
@@ -1,38 +1,101 @@
 use smallvec::SmallVec;
 
-#[derive(Debug, Clone, Copy)]
+use super::cooldown::UsageLimit;
+use super::damage::DamageType;
+use super::effect::Effect;
+
+/// Index of an attack in its unit class' `attacks`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct AttackId(u16);
 
-struct AttackDefinition {
-    base_damage: i32,  // negative for heal
-    damage_type: DamageProfile,
-    range: AttackRange,
-    aoe: AoePattern,
-    //effects: SmallVec<Effect, 3>,
-    target: TargetFilter,
+impl AttackId {
+    pub fn new(val: u16) -> Self { AttackId(val) }
+    pub fn index(&self) -> usize { self.0 as usize }
+}
+
+#[derive(Debug, Clone)]
+pub struct AttackDefinition {
+    pub base_damage: u32,  // healing is done by abilities, see `AbilityEffect::Heal`
+    pub damage_type: DamageProfile,
+    pub armor_penetration: f32,  // taken off each of the target's resistances
+    pub range: AttackRange,
+    pub aoe: AoePattern,
+    pub effects: SmallVec<[Effect; 3]>,  // applied to every unit hit
+    pub target: TargetFilter,
+    pub needs_line_of_sight: bool,  // false for indirect fire (catapults, spells)
+    pub hit_chance: f32,  // 0..1, before the target's evasion
+    pub crit_chance: f32,  // 0..1, rolled once the attack hits
+    pub crit_multiplier: f32,
+    pub displacement: Option<Displacement>,  // applied to the picked target if it survives
+    pub usage: UsageLimit,
 }
 
-enum TargetFilter {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetFilter {
     Enemy,
     Ally,
     Any,
 }
 
-struct DamageProfile {
-    pierce: u8,
-    blunt: u8,
-    slash: u8,
+impl TargetFilter {
+    /// Whether a unit that is (or isn't) allied to the attacker may be targeted
+    pub fn allows(&self, allied: bool) -> bool {
+        match self {
+            TargetFilter::Enemy => !allied,
+            TargetFilter::Ally => allied,
+            TargetFilter::Any => true,
+        }
+    }
 }
 
-enum AoePattern {
+/// Relative weight of each damage type in an attack, see `combat::damage`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DamageProfile {
+    pub pierce: u8,
+    pub blunt: u8,
+    pub slash: u8,
+    pub fire: u8,
+    pub ice: u8,
+}
+
+impl DamageProfile {
+    pub fn weight(&self, damage_type: DamageType) -> u8 {
+        match damage_type {
+            DamageType::Pierce => self.pierce,
+            DamageType::Blunt => self.blunt,
+            DamageType::Slash => self.slash,
+            DamageType::Fire => self.fire,
+            DamageType::Ice => self.ice,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AoePattern {
     Single,
-    Sides,  // left and right
+    Sides,  // left and right of the target, across the attacker's facing
     Radius(u8),  // affects everyone except self
     Line(u8),  // affects behind in line
     Cone(u8),
 }
 
-struct AttackRange {
-    innerRadius: u8,
-    outerRadius: u8,
+/// Forced movement, distinct from a unit's own moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Displacement {
+    Push(u8),  // away from the attacker, tile by tile
+    Pull(u8),  // toward the attacker, stops next to it
+    Swap,      // attacker and target trade places
+}
+
+/// Hex distances, both inclusive, at which a target can be picked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttackRange {
+    pub inner_radius: u8,
+    pub outer_radius: u8,
+}
+
+impl AttackRange {
+    pub fn contains(&self, distance: i16) -> bool {
+        distance >= self.inner_radius as i16 && distance <= self.outer_radius as i16
+    }
 }
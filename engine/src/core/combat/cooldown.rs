@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use super::ability::AbilityId;
+use super::attack::AttackId;
+
+/// One of a unit class' attacks or abilities
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActionKey {
+    Attack(AttackId),
+    Ability(AbilityId),
+}
+
+/// What a cooldown counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CooldownTick {
+    /// Turns of the unit that used it
+    #[default]
+    OwnTurn,
+    /// Rounds of the match
+    Round,
+}
+
+/// How often an attack or ability may be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UsageLimit {
+    pub cooldown: u8,  // ticks until it can be used again, 0 for none
+    pub tick: CooldownTick,
+    pub charges: Option<u8>,  // uses for the whole match, None for unlimited
+}
+
+impl UsageLimit {
+    pub fn cooldown(cooldown: u8, tick: CooldownTick) -> Self {
+        UsageLimit { cooldown, tick, charges: None }
+    }
+
+    pub fn charges(charges: u8) -> Self {
+        UsageLimit { charges: Some(charges), ..Self::default() }
+    }
+}
+
+/// Where an attack or ability of a unit stands, for the UI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsageStatus {
+    pub cooldown: u8,  // ticks left, 0 when ready
+    pub charges_left: Option<u8>,  // None for unlimited
+}
+
+impl UsageStatus {
+    pub fn is_ready(&self) -> bool {
+        self.cooldown == 0 && self.charges_left != Some(0)
+    }
+}
+
+/// Running cooldowns and spent charges of one unit
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cooldowns {
+    remaining: HashMap<ActionKey, (u8, CooldownTick)>,  // only running ones
+    used: HashMap<ActionKey, u8>,  // only for actions with charges
+}
+
+impl Cooldowns {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn status(&self, key: ActionKey, limit: &UsageLimit) -> UsageStatus {
+        UsageStatus {
+            cooldown: self.remaining.get(&key).map_or(0, |&(ticks, _)| ticks),
+            charges_left: limit.charges.map(|c| c.saturating_sub(self.used.get(&key).copied().unwrap_or(0))),
+        }
+    }
+
+    /// Start the cooldown and spend a charge of `key`
+    pub fn record_use(&mut self, key: ActionKey, limit: &UsageLimit) {
+        if limit.cooldown > 0 {
+            self.remaining.insert(key, (limit.cooldown, limit.tick));
+        }
+        if limit.charges.is_some() {
+            *self.used.entry(key).or_insert(0) += 1;
+        }
+    }
+
+    /// Count down every cooldown measured in `tick`
+    pub fn tick(&mut self, tick: CooldownTick) {
+        self.remaining.retain(|_, (ticks, kind)| {
+            if *kind == tick {
+                *ticks = ticks.saturating_sub(1);
+            }
+            *ticks > 0
+        });
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cooldowns_tick_by_kind() {
+        let (slash, bomb) = (ActionKey::Attack(AttackId::new(0)), ActionKey::Ability(AbilityId::new(0)));
+        let slash_limit = UsageLimit::cooldown(1, CooldownTick::OwnTurn);
+        let bomb_limit = UsageLimit { cooldown: 2, tick: CooldownTick::Round, charges: Some(2) };
+
+        let mut cooldowns = Cooldowns::new();
+        cooldowns.record_use(slash, &slash_limit);
+        cooldowns.record_use(bomb, &bomb_limit);
+        assert!(!cooldowns.status(slash, &slash_limit).is_ready());
+
+        cooldowns.tick(CooldownTick::OwnTurn);
+        assert!(cooldowns.status(slash, &slash_limit).is_ready());
+        assert_eq!(cooldowns.status(bomb, &bomb_limit), UsageStatus { cooldown: 2, charges_left: Some(1) });
+
+        cooldowns.tick(CooldownTick::Round);
+        cooldowns.tick(CooldownTick::Round);
+        assert!(cooldowns.status(bomb, &bomb_limit).is_ready());
+
+        cooldowns.record_use(bomb, &bomb_limit);
+        cooldowns.tick(CooldownTick::Round);
+        cooldowns.tick(CooldownTick::Round);
+        assert_eq!(cooldowns.status(bomb, &bomb_limit), UsageStatus { cooldown: 0, charges_left: Some(0) });
+    }
+}
@@ -0,0 +1,128 @@
+//! Damage types, armor and the mitigation formula.
+//!
+//! An attack's `DamageProfile` gives a weight to each damage type and its
+//! base damage is split in those proportions. Each part is reduced by the
+//! target's resistance to that type, after the attack's armor penetration:
+//!
+//! ```text
+//! share(t)      = weight(t) / sum of weights
+//! resistance(t) = armor(t) - penetration, but never below 0 if armor(t) >= 0
+//! damage        = base * multiplier * sum over t of share(t) * (1 - resistance(t))
+//! ```
+//!
+//! Resistances are at most 1 (immune). Negative resistances are weaknesses,
+//! -0.5 takes 50% extra, and penetration doesn't make them worse. A profile
+//! with no weights at all is untyped and ignores armor.
+//!
+//! E.g. a spear (pierce 1, penetration 0.3) against a knight (pierce 0.5)
+//! deals 80% of its damage; a sword (slash 1) against the same knight
+//! (slash 0.6) only 40%.
+
+use super::attack::DamageProfile;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DamageType {
+    Pierce,
+    Blunt,
+    Slash,
+    Fire,
+    Ice,
+}
+
+impl DamageType {
+    pub const ALL: [DamageType; 5] = [
+        DamageType::Pierce,
+        DamageType::Blunt,
+        DamageType::Slash,
+        DamageType::Fire,
+        DamageType::Ice,
+    ];
+}
+
+/// Resistance of a unit class to each damage type, see the module docs
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Armor {
+    pub pierce: f32,
+    pub blunt: f32,
+    pub slash: f32,
+    pub fire: f32,
+    pub ice: f32,
+}
+
+impl Armor {
+    /// The same resistance to everything
+    pub fn uniform(resistance: f32) -> Self {
+        Armor {
+            pierce: resistance,
+            blunt: resistance,
+            slash: resistance,
+            fire: resistance,
+            ice: resistance,
+        }
+    }
+
+    pub fn resistance(&self, damage_type: DamageType) -> f32 {
+        match damage_type {
+            DamageType::Pierce => self.pierce,
+            DamageType::Blunt => self.blunt,
+            DamageType::Slash => self.slash,
+            DamageType::Fire => self.fire,
+            DamageType::Ice => self.ice,
+        }
+    }
+
+    /// Resistance left against an attack with `penetration`
+    pub fn effective_resistance(&self, damage_type: DamageType, penetration: f32) -> f32 {
+        let resistance = self.resistance(damage_type).min(1.0);
+        if resistance <= 0.0 {
+            resistance
+        } else {
+            (resistance - penetration.max(0.0)).max(0.0)
+        }
+    }
+
+    /// Fraction of `profile` damage that gets through, usually 0..1 and above 1 on weaknesses
+    pub fn damage_factor(&self, profile: &DamageProfile, penetration: f32) -> f32 {
+        let total: u32 = DamageType::ALL.iter().map(|&t| profile.weight(t) as u32).sum();
+        if total == 0 {
+            return 1.0;
+        }
+
+        DamageType::ALL
+            .iter()
+            .map(|&t| profile.weight(t) as f32 / total as f32 * (1.0 - self.effective_resistance(t, penetration)))
+            .sum()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(pierce: u8, blunt: u8, slash: u8, fire: u8) -> DamageProfile {
+        DamageProfile { pierce, blunt, slash, fire, ice: 0 }
+    }
+
+    #[test]
+    fn test_spear_against_knight() {
+        let knight = Armor { pierce: 0.5, blunt: 0.2, slash: 0.6, fire: 0.0, ice: 0.0 };
+        let spear = profile(1, 0, 0, 0);
+        let sword = profile(0, 0, 1, 0);
+
+        assert!((knight.damage_factor(&spear, 0.3) - 0.8).abs() < 1e-6);
+        assert!((knight.damage_factor(&sword, 0.0) - 0.4).abs() < 1e-6);
+        assert!((knight.damage_factor(&spear, 2.0) - 1.0).abs() < 1e-6);  // can't go past no armor
+    }
+
+    #[test]
+    fn test_mixed_profile_and_weakness() {
+        let ice_golem = Armor { fire: -0.5, ice: 1.0, ..Armor::uniform(0.5) };
+
+        // Half fire at 150%, half blunt at 50%
+        assert!((ice_golem.damage_factor(&profile(0, 1, 0, 1), 0.0) - 1.0).abs() < 1e-6);
+        assert!((ice_golem.damage_factor(&profile(0, 0, 0, 1), 1.0) - 1.5).abs() < 1e-6);
+        assert_eq!(ice_golem.damage_factor(&DamageProfile { ice: 3, ..profile(0, 0, 0, 0) }, 0.0), 0.0);
+        assert_eq!(ice_golem.damage_factor(&profile(0, 0, 0, 0), 0.0), 1.0);
+    }
+}
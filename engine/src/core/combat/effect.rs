@@ -1,3 +1,8 @@
-enum Effect {
-    
-}
\ No newline at end of file
+/// Status effect carried by a unit, usually applied by an attack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    Stun { turns: u8 },
+    Poison { damage: u32, turns: u8 },
+    Slow { amount: u8, turns: u8 },
+    Regeneration { amount: u32, turns: u8 },  // healed at the start of each own turn
+}
@@ -1,4 +1,13 @@
+pub mod ability;
 pub mod attack;
+pub mod cooldown;
+pub mod damage;
 pub mod effect;
+pub mod reaction;
 
-pub use attack::{AttackId};
\ No newline at end of file
+pub use ability::{AbilityDefinition, AbilityEffect, AbilityId, AbilityTarget, AbilityTargeting};
+pub use attack::{AoePattern, AttackDefinition, AttackId, AttackRange, DamageProfile, Displacement, TargetFilter};
+pub use cooldown::{ActionKey, CooldownTick, Cooldowns, UsageLimit, UsageStatus};
+pub use damage::{Armor, DamageType};
+pub use effect::Effect;
+pub use reaction::{ReactionDefinition, ReactionTrigger};
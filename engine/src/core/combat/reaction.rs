@@ -0,0 +1,19 @@
+use super::attack::AttackId;
+
+/// What makes a unit act outside its own turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReactionTrigger {
+    /// An enemy attacked this unit: counterattack
+    Attacked,
+    /// An adjacent enemy walked away: opportunity attack
+    EnemyLeaves,
+    /// An enemy hit an adjacent ally: guard
+    AllyHit,
+}
+
+/// A reaction a unit class can take, answering with one of its own attacks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReactionDefinition {
+    pub trigger: ReactionTrigger,
+    pub attack: AttackId,  // aimed at the enemy that triggered it
+}
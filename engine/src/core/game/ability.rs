@@ -0,0 +1,313 @@
+//! Ability resolution.
+//!
+//! Like attacks, `resolve_ability` works out everything an ability does
+//! without touching the game and `perform_ability` applies that result.
+//! Abilities never miss, so there are no rolls involved.
+
+use crate::core::combat::{AbilityDefinition, AbilityEffect, AbilityId, AbilityTarget, AbilityTargeting, ActionKey};
+use crate::core::geom::Position;
+use crate::core::player::PlayerId;
+use crate::core::unit::{Unit, UnitId};
+
+use super::healing::{Heal, ShieldChange};
+use super::resolution::{aoe_positions, AttackRoll, Hit};
+use super::state::{GameError, ResolvedChange};
+use super::Game;
+
+/// What an ability did, enough for clients to replay or roll it back
+#[derive(Debug, Clone, PartialEq)]
+pub struct AbilityResolution {
+    pub caster: UnitId,
+    pub ability: AbilityId,
+    pub target: AbilityTarget,
+    pub cost: u32,  // gold paid by the caster's owner
+    pub hits: Vec<Hit>,
+    pub heals: Vec<Heal>,  // after the hits, logged separately
+    pub shields: Vec<ShieldChange>,  // likewise
+    pub teleport: Option<(Position, Position)>,  // caster moved from, to
+}
+
+impl Game {
+    /// Check a proposed ability use by `player` with the unit whose turn it is
+    pub fn validate_ability(&self, player: PlayerId, ability: AbilityId, target: AbilityTarget) -> Result<(), GameError> {
+        let caster = self.current_unit().ok_or(GameError::WrongPhase)?;
+        let unit = self.get_unit(caster).ok_or(GameError::InvalidUnit)?;
+        if unit.owner != player {
+            return Err(GameError::NotYourTurn);
+        }
+
+        self.check_ability(unit, ability, &target).map(|_| ())
+    }
+
+    /// Everything `caster` using `ability` on `target` would do
+    pub fn resolve_ability(&self, caster: UnitId, ability: AbilityId, target: AbilityTarget) -> Result<AbilityResolution, GameError> {
+        let unit = self.get_unit(caster).ok_or(GameError::InvalidUnit)?;
+        let definition = self.check_ability(unit, ability, &target)?;
+        let center = self.ability_center(unit, &target).ok_or(GameError::InvalidTarget)?;
+
+        let affected: Vec<&Unit> = aoe_positions(unit.get_pos(), unit.facing, center, definition.area)
+            .into_iter()
+            .filter_map(|pos| self.grid().get_occupancy(pos))
+            .filter_map(|id| self.get_unit(id))
+            .filter(|victim| self.target_filter_allows(definition.affects, unit.owner, victim.owner))
+            .collect();
+
+        let mut hits = Vec::new();
+        let mut heals = Vec::new();
+        let mut shields = Vec::new();
+        for victim in affected {
+            let hit = ability_hit(definition, victim, self.can_damage(unit.owner, victim.owner));
+            let health = hit.as_ref().map_or(victim.health, |h| h.health_after);
+            hits.extend(hit);
+
+            let (healing, shield) = definition.effects.iter().fold((0, 0), |(heal, shield), effect| match *effect {
+                AbilityEffect::Heal(amount) => (heal + amount, shield),
+                AbilityEffect::Shield(amount) => (heal, shield.max(amount)),
+                _ => (heal, shield),
+            });
+            if healing > 0 && health > 0 {
+                heals.push(self.compute_heal(victim, health, healing));
+            }
+            shields.extend(self.compute_shield(victim, shield));
+        }
+
+        let teleport = definition.effects
+            .contains(&AbilityEffect::Teleport)
+            .then_some((unit.get_pos(), center));
+
+        Ok(AbilityResolution {
+            caster,
+            ability,
+            target,
+            cost: definition.cost,
+            hits,
+            heals,
+            shields,
+            teleport,
+        })
+    }
+
+    /// Resolve an ability for real: apply it, log it and clear the dead
+    pub(super) fn perform_ability(&mut self, caster: UnitId, ability: AbilityId, target: AbilityTarget) -> Result<(), GameError> {
+        let resolution = self.resolve_ability(caster, ability, target)?;
+
+        self.apply_ability(&resolution);
+        let (heals, shields) = (resolution.heals.clone(), resolution.shields.clone());
+        self.log_change(ResolvedChange::Ability { resolution });
+
+        for heal in heals {
+            self.apply_heal(&heal);
+            self.log_change(ResolvedChange::Heal { healer: caster, heal });
+        }
+        for change in shields {
+            self.apply_shield(&change);
+            self.log_change(ResolvedChange::Shield { source: Some(caster), change });
+        }
+        self.remove_dead_units();
+
+        Ok(())
+    }
+
+    /// Cost, cooldown, hits and teleport; heals and shields have their own changes
+    pub(super) fn apply_ability(&mut self, resolution: &AbilityResolution) {
+        let Some(owner) = self.get_unit(resolution.caster).map(|u| u.owner) else { return };
+        self.record_usage(resolution.caster, ActionKey::Ability(resolution.ability));
+
+        if let Some(player) = self.get_player_mut(owner) {
+            player.spend(resolution.cost);
+        }
+
+        self.apply_hits(&resolution.hits);
+
+        if let Some((from, to)) = resolution.teleport {
+            self.grid_mut().move_occupancy(from, to).expect("teleport target was validated");
+            if let Some(caster) = self.get_unit_mut(resolution.caster) {
+                caster.change_pos(to);
+            }
+        }
+    }
+
+    fn check_ability(&self, unit: &Unit, ability: AbilityId, target: &AbilityTarget) -> Result<&AbilityDefinition, GameError> {
+        let definition = self.registry()
+            .ability(unit.class, ability)
+            .ok_or(GameError::UnknownAbility)?;
+
+        if !definition.targeting.accepts(target) {
+            return Err(GameError::InvalidTarget);
+        }
+        self.check_usage(unit, ActionKey::Ability(ability))?;
+        if !self.get_player(unit.owner).is_some_and(|p| p.can_afford(definition.cost)) {
+            return Err(GameError::NotEnoughResources);
+        }
+
+        match (definition.targeting, *target) {
+            (AbilityTargeting::Unit { range, filter }, AbilityTarget::Unit(id)) => {
+                let other = self.get_unit(id).ok_or(GameError::InvalidUnit)?;
+                if !self.target_filter_allows(filter, unit.owner, other.owner) {
+                    return Err(GameError::InvalidTarget);
+                }
+                if !range.contains(unit.get_pos().distance(other.get_pos())) {
+                    return Err(GameError::OutOfRange);
+                }
+            }
+            (AbilityTargeting::Tile { range }, AbilityTarget::Tile(pos)) => {
+                if !self.grid().in_bounds(pos) {
+                    return Err(GameError::InvalidPosition);
+                }
+                if !range.contains(unit.get_pos().distance(pos)) {
+                    return Err(GameError::OutOfRange);
+                }
+            }
+            _ => {}
+        }
+
+        if definition.effects.contains(&AbilityEffect::Teleport) {
+            let to = self.ability_center(unit, target).ok_or(GameError::InvalidTarget)?;
+            if !self.grid().get_terrain_type(to).is_some_and(|t| t.is_walkable()) {
+                return Err(GameError::InvalidPosition);
+            }
+            if self.grid().get_occupancy(to).is_some_and(|id| id != unit.id) {
+                return Err(GameError::TileOccupied);
+            }
+        }
+
+        Ok(definition)
+    }
+
+    /// Tile the ability's area is centered on
+    fn ability_center(&self, caster: &Unit, target: &AbilityTarget) -> Option<Position> {
+        match *target {
+            AbilityTarget::Myself => Some(caster.get_pos()),
+            AbilityTarget::Unit(id) => self.get_unit(id).map(|u| u.get_pos()),
+            AbilityTarget::Tile(pos) => Some(pos),
+            AbilityTarget::Direction(dir) => Some(caster.get_pos().offset(dir.dir_vec())),
+        }
+    }
+}
+
+/// What the ability's effects do to one unit, if anything
+fn ability_hit(definition: &AbilityDefinition, victim: &Unit, can_damage: bool) -> Option<Hit> {
+    let mut damage = 0u32;
+    let mut effects = Vec::new();
+
+    for effect in &definition.effects {
+        match *effect {
+            AbilityEffect::Damage(amount) if can_damage => damage += amount,
+            AbilityEffect::Status(status) => effects.push(status),
+            _ => {}
+        }
+    }
+    if damage == 0 && effects.is_empty() {
+        return None;
+    }
+
+    Some(Hit::new(victim.id, AttackRoll::certain(), damage, victim.health, victim.shield, effects))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::combat::{AoePattern, AttackRange, CooldownTick, Effect, TargetFilter, UsageLimit};
+    use crate::core::game::state::ProposedAction;
+    use crate::core::game::testing::{self, unit_class};
+    use crate::core::geom::Direction;
+    use crate::core::unit::{UnitClassId, UnitDefinition};
+
+    fn mage_game() -> Game {
+        let fireball = AbilityDefinition {
+            name: "Fireball".to_string(),
+            targeting: AbilityTargeting::Tile { range: AttackRange { inner_radius: 2, outer_radius: 4 } },
+            area: AoePattern::Radius(1),
+            affects: TargetFilter::Any,
+            cost: 3,
+            usage: UsageLimit::cooldown(2, CooldownTick::OwnTurn),
+            effects: vec![AbilityEffect::Damage(4)],
+        };
+        let blink = AbilityDefinition {
+            name: "Blink".to_string(),
+            targeting: AbilityTargeting::Tile { range: AttackRange { inner_radius: 1, outer_radius: 3 } },
+            area: AoePattern::Single,
+            affects: TargetFilter::Any,
+            cost: 0,
+            usage: UsageLimit::default(),
+            effects: vec![AbilityEffect::Teleport],
+        };
+        let breath = AbilityDefinition {
+            name: "Frost breath".to_string(),
+            targeting: AbilityTargeting::Direction,
+            area: AoePattern::Line(1),
+            affects: TargetFilter::Enemy,
+            cost: 0,
+            usage: UsageLimit::default(),
+            effects: vec![AbilityEffect::Status(Effect::Slow { amount: 1, turns: 2 })],
+        };
+
+        testing::game()
+            .class(UnitDefinition { abilities: vec![fireball, blink, breath], ..unit_class("Mage") })
+            .gold(PlayerId::new(1), 5)
+            .build()
+    }
+
+    #[test]
+    fn test_fireball_costs_and_cools_down() {
+        let mut game = mage_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(1, 1), p1);
+        game.spawn_unit(class, Position::new(4, 1), p2);
+        game.spawn_unit(class, Position::new(5, 1), p2);
+        game.commit_turn();
+
+        let fireball = AbilityId::new(0);
+        let target = AbilityTarget::Tile(Position::new(4, 1));
+        assert!(matches!(game.validate_ability(p1, fireball, AbilityTarget::Myself), Err(GameError::InvalidTarget)));
+        assert!(matches!(
+            game.validate_ability(p1, fireball, AbilityTarget::Tile(Position::new(2, 1))),
+            Err(GameError::OutOfRange)
+        ));
+
+        game.handle_action(p1, ProposedAction::Ability { ability: fireball, target }).unwrap();
+
+        assert_eq!(game.get_unit(UnitId(1)).unwrap().health, 6);
+        assert_eq!(game.get_unit(UnitId(2)).unwrap().health, 6);
+        assert_eq!(game.get_player(p1).unwrap().gold(), 2);
+        assert_eq!(game.usage_status(UnitId(0), ActionKey::Ability(fireball)).unwrap().cooldown, 2);
+        assert!(matches!(game.current_changes().last(), Some(ResolvedChange::Ability { resolution }) if resolution.hits.len() == 2));
+
+        // Two of the mage's own turns later it's ready again
+        assert!(matches!(game.validate_ability(p1, fireball, target), Err(GameError::OnCooldown)));
+        game.get_unit_mut(UnitId(0)).unwrap().cooldowns.tick(CooldownTick::OwnTurn);
+        game.get_unit_mut(UnitId(0)).unwrap().cooldowns.tick(CooldownTick::OwnTurn);
+        assert!(matches!(game.validate_ability(p1, fireball, target), Err(GameError::NotEnoughResources)));
+    }
+
+    #[test]
+    fn test_blink_and_breath() {
+        let mut game = mage_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(1, 1), p1);
+        game.spawn_unit(class, Position::new(5, 1), p2);
+        game.spawn_unit(class, Position::new(6, 1), p2);
+        game.commit_turn();
+
+        let blink = AbilityId::new(1);
+        assert!(matches!(
+            game.validate_ability(p1, blink, AbilityTarget::Tile(Position::new(5, 1))),
+            Err(GameError::OutOfRange)
+        ));
+        game.handle_action(p1, ProposedAction::Ability { ability: blink, target: AbilityTarget::Tile(Position::new(4, 1)) }).unwrap();
+        assert_eq!(game.get_unit(UnitId(0)).unwrap().get_pos(), Position::new(4, 1));
+        assert_eq!(game.grid().get_occupancy(Position::new(4, 1)), Some(UnitId(0)));
+        assert_eq!(game.grid().get_occupancy(Position::new(1, 1)), None);
+
+        let breath = AbilityId::new(2);
+        let resolution = game.resolve_ability(UnitId(0), breath, AbilityTarget::Direction(Direction::Right)).unwrap();
+        let slowed: Vec<UnitId> = resolution.hits.iter().map(|h| h.unit).collect();
+        assert_eq!(slowed, vec![UnitId(1), UnitId(2)]);
+        assert_eq!(resolution.hits[0].effects, vec![Effect::Slow { amount: 1, turns: 2 }]);
+    }
+}
@@ -0,0 +1,120 @@
+//! Cooldowns and charges of attacks and abilities.
+//!
+//! Each unit tracks its own in `Unit::cooldowns`, so they are part of every
+//! `RoundSnapshot`. Own-turn cooldowns tick when the unit's turn begins,
+//! round cooldowns when a round ends.
+
+use crate::core::combat::{ActionKey, AbilityId, AttackId, UsageStatus};
+use crate::core::unit::{Unit, UnitId};
+
+use super::state::GameError;
+use super::Game;
+
+impl Game {
+    /// Cooldown and charges left of one of `unit`'s attacks or abilities
+    pub fn usage_status(&self, unit: UnitId, key: ActionKey) -> Option<UsageStatus> {
+        let unit = self.get_unit(unit)?;
+        let limit = self.registry().usage(unit.class, key)?;
+
+        Some(unit.cooldowns.status(key, limit))
+    }
+
+    /// Every attack and ability of `unit` with its status, attacks first
+    pub fn usage_overview(&self, unit: UnitId) -> Vec<(ActionKey, UsageStatus)> {
+        let Some(definition) = self.get_unit(unit).and_then(|u| self.registry().get(u.class)) else {
+            return Vec::new();
+        };
+
+        let attacks = (0..definition.attacks.len()).map(|i| ActionKey::Attack(AttackId::new(i as u16)));
+        let abilities = (0..definition.abilities.len()).map(|i| ActionKey::Ability(AbilityId::new(i as u16)));
+
+        attacks
+            .chain(abilities)
+            .filter_map(|key| self.usage_status(unit, key).map(|status| (key, status)))
+            .collect()
+    }
+
+    pub(super) fn check_usage(&self, unit: &Unit, key: ActionKey) -> Result<(), GameError> {
+        let Some(status) = self.usage_status(unit.id, key) else { return Ok(()) };
+
+        if status.cooldown > 0 {
+            return Err(GameError::OnCooldown);
+        }
+        if status.charges_left == Some(0) {
+            return Err(GameError::NoChargesLeft);
+        }
+        Ok(())
+    }
+
+    pub(super) fn record_usage(&mut self, unit: UnitId, key: ActionKey) {
+        let Some(class) = self.get_unit(unit).map(|u| u.class) else { return };
+        let Some(limit) = self.registry().usage(class, key).copied() else { return };
+
+        if let Some(unit) = self.get_unit_mut(unit) {
+            unit.cooldowns.record_use(key, &limit);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::combat::{AttackDefinition, AttackRange, CooldownTick, UsageLimit};
+    use crate::core::game::state::ProposedAction;
+    use crate::core::game::testing::{self, unit_class};
+    use crate::core::geom::Position;
+    use crate::core::player::PlayerId;
+    use crate::core::unit::{UnitClassId, UnitDefinition};
+
+    fn attack(usage: UsageLimit) -> AttackDefinition {
+        AttackDefinition {
+            base_damage: 1,
+            range: AttackRange { inner_radius: 1, outer_radius: 2 },
+            usage,
+            ..testing::attack()
+        }
+    }
+
+    /// Grenadiers throw one grenade per match and volley every other round
+    fn grenadier_game() -> Game {
+        testing::game()
+            .class(UnitDefinition {
+                attacks: vec![
+                    attack(UsageLimit::charges(1)),
+                    attack(UsageLimit::cooldown(1, CooldownTick::Round)),
+                ],
+                ..unit_class("Grenadier")
+            })
+            .build()
+    }
+
+    #[test]
+    fn test_charges_and_round_cooldowns() {
+        let mut game = grenadier_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+        let (grenade, volley) = (AttackId::new(0), AttackId::new(1));
+
+        game.spawn_unit(class, Position::new(2, 2), p1);
+        game.spawn_unit(class, Position::new(4, 2), p2);
+        game.commit_turn();
+
+        game.handle_action(p1, ProposedAction::Attack { target: UnitId(1), attack: grenade }).unwrap();
+        game.handle_action(p1, ProposedAction::Attack { target: UnitId(1), attack: volley }).unwrap();
+
+        assert!(matches!(game.validate_attack(p1, UnitId(1), grenade), Err(GameError::NoChargesLeft)));
+        assert!(matches!(game.validate_attack(p1, UnitId(1), volley), Err(GameError::OnCooldown)));
+        assert!(game.legal_targets(UnitId(0), volley).is_empty());
+        assert_eq!(game.usage_overview(UnitId(0)), vec![
+            (ActionKey::Attack(grenade), UsageStatus { cooldown: 0, charges_left: Some(0) }),
+            (ActionKey::Attack(volley), UsageStatus { cooldown: 1, charges_left: None }),
+        ]);
+
+        // Own turns don't count for a round cooldown, the round ending does
+        game.commit_turn();
+        assert_eq!(game.usage_status(UnitId(0), ActionKey::Attack(volley)).unwrap().cooldown, 1);
+        game.commit_turn();
+        assert!(game.usage_status(UnitId(0), ActionKey::Attack(volley)).unwrap().is_ready());
+    }
+}
@@ -0,0 +1,216 @@
+//! Healing, shields and regeneration.
+//!
+//! Healing never goes above a unit's max health, anything past it is lost
+//! and reported as `overheal`. Shields soak damage before health, don't
+//! stack (a new shield only replaces a weaker one) and expire when the round
+//! ends. Regeneration heals at the start of each of the unit's own turns.
+//! Each of these is logged as its own `ResolvedChange`.
+
+use crate::core::combat::Effect;
+use crate::core::unit::{Unit, UnitId};
+
+use super::state::ResolvedChange;
+use super::Game;
+
+/// Health restored to a unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Heal {
+    pub unit: UnitId,
+    pub amount: u32,  // actually restored
+    pub overheal: u32,  // lost to the max health cap
+    pub health_before: u32,
+    pub health_after: u32,
+}
+
+/// A unit's shield going up, or down when it expires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShieldChange {
+    pub unit: UnitId,
+    pub shield_before: u32,
+    pub shield_after: u32,
+}
+
+impl Game {
+    /// Health `unit` spawned with and can be healed back to
+    pub fn max_health(&self, unit: &Unit) -> u32 {
+        self.registry()
+            .get(unit.class)
+            .map_or(unit.health, |d| d.base_health.max(0) as u32)
+    }
+
+    /// Healing `unit`, currently at `health`, by `amount`
+    pub fn compute_heal(&self, unit: &Unit, health: u32, amount: u32) -> Heal {
+        let max_health = self.max_health(unit).max(health);
+        let health_after = health.saturating_add(amount).min(max_health);
+
+        Heal {
+            unit: unit.id,
+            amount: health_after - health,
+            overheal: amount - (health_after - health),
+            health_before: health,
+            health_after,
+        }
+    }
+
+    /// Giving `unit` a shield of `amount`, if that's more than it has
+    pub fn compute_shield(&self, unit: &Unit, amount: u32) -> Option<ShieldChange> {
+        (amount > unit.shield).then_some(ShieldChange {
+            unit: unit.id,
+            shield_before: unit.shield,
+            shield_after: amount,
+        })
+    }
+
+    pub(super) fn apply_heal(&mut self, heal: &Heal) {
+        if let Some(unit) = self.get_unit_mut(heal.unit) {
+            unit.health = heal.health_after;
+        }
+    }
+
+    pub(super) fn apply_shield(&mut self, change: &ShieldChange) {
+        if let Some(unit) = self.get_unit_mut(change.unit) {
+            unit.shield = change.shield_after;
+        }
+    }
+
+    /// Heal `unit` from its regeneration effects and count them down
+    pub(super) fn regenerate(&mut self, unit: UnitId) {
+        let Some(regenerating) = self.get_unit_mut(unit) else { return };
+
+        let mut amount = 0;
+        for effect in regenerating.effects.iter_mut() {
+            if let Effect::Regeneration { amount: per_turn, turns } = effect {
+                amount += *per_turn;
+                *turns = turns.saturating_sub(1);
+            }
+        }
+        regenerating.effects.retain(|e| !matches!(e, Effect::Regeneration { turns: 0, .. }));
+
+        let Some(regenerating) = self.get_unit(unit) else { return };
+        if amount == 0 || regenerating.health == 0 {
+            return;
+        }
+
+        let heal = self.compute_heal(regenerating, regenerating.health, amount);
+        self.apply_heal(&heal);
+        self.log_change(ResolvedChange::Regeneration { heal });
+    }
+
+    /// Drop every shield, at the end of the round
+    pub(super) fn expire_shields(&mut self) {
+        let mut shielded: Vec<UnitId> = self.units_iter()
+            .filter(|u| u.shield > 0)
+            .map(|u| u.id)
+            .collect();
+        shielded.sort();
+
+        for unit in shielded {
+            let shield_before = self.get_unit(unit).map_or(0, |u| u.shield);
+            let change = ShieldChange { unit, shield_before, shield_after: 0 };
+
+            self.apply_shield(&change);
+            self.log_change(ResolvedChange::Shield { source: None, change });
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::combat::{
+        AbilityDefinition, AbilityEffect, AbilityId, AbilityTarget, AbilityTargeting, AoePattern,
+        AttackId, AttackRange, TargetFilter, UsageLimit,
+    };
+    use crate::core::game::state::ProposedAction;
+    use crate::core::game::testing::{self, attack, unit_class};
+    use crate::core::geom::Position;
+    use crate::core::player::PlayerId;
+    use crate::core::unit::{UnitClassId, UnitDefinition};
+
+    fn ability(name: &str, targeting: AbilityTargeting, effect: AbilityEffect) -> AbilityDefinition {
+        AbilityDefinition {
+            name: name.to_string(),
+            targeting,
+            area: AoePattern::Single,
+            affects: TargetFilter::Ally,
+            cost: 0,
+            usage: UsageLimit::default(),
+            effects: vec![effect],
+        }
+    }
+
+    /// Clerics hit for 3, mend allies for 5 and ward themselves for 4
+    fn cleric_game() -> Game {
+        let mend = ability(
+            "Mend",
+            AbilityTargeting::Unit { range: AttackRange { inner_radius: 1, outer_radius: 2 }, filter: TargetFilter::Ally },
+            AbilityEffect::Heal(5),
+        );
+        let ward = ability("Ward", AbilityTargeting::SelfOnly, AbilityEffect::Shield(4));
+
+        testing::game()
+            .class(UnitDefinition { attacks: vec![attack()], abilities: vec![mend, ward], ..unit_class("Cleric") })
+            .build()
+    }
+
+    #[test]
+    fn test_heal_is_capped() {
+        let mut game = cleric_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(2, 2), p1);
+        game.spawn_unit(class, Position::new(3, 2), p1);
+        game.spawn_unit(class, Position::new(6, 6), p2);
+        game.get_unit_mut(UnitId(1)).unwrap().health = 7;
+        game.commit_turn();
+
+        let mend = ProposedAction::Ability { ability: AbilityId::new(0), target: AbilityTarget::Unit(UnitId(1)) };
+        game.handle_action(p1, mend).unwrap();
+
+        assert_eq!(game.get_unit(UnitId(1)).unwrap().health, 10);
+        let heal = Heal { unit: UnitId(1), amount: 3, overheal: 2, health_before: 7, health_after: 10 };
+        assert!(matches!(game.current_changes().last(), Some(ResolvedChange::Heal { healer: UnitId(0), heal: h }) if *h == heal));
+    }
+
+    #[test]
+    fn test_shield_soaks_then_expires() {
+        let mut game = cleric_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(2, 2), p1);
+        game.spawn_unit(class, Position::new(3, 2), p2);
+        game.commit_turn();
+
+        game.handle_action(p1, ProposedAction::Ability { ability: AbilityId::new(1), target: AbilityTarget::Myself }).unwrap();
+        assert_eq!(game.get_unit(UnitId(0)).unwrap().shield, 4);
+        game.commit_turn();
+
+        game.handle_action(p2, ProposedAction::Attack { target: UnitId(0), attack: AttackId::new(0) }).unwrap();
+        let warded = game.get_unit(UnitId(0)).unwrap();
+        assert_eq!((warded.health, warded.shield), (10, 1));
+
+        game.commit_turn();  // round over
+        assert_eq!(game.get_unit(UnitId(0)).unwrap().shield, 0);
+    }
+
+    #[test]
+    fn test_regeneration_on_own_turn() {
+        let mut game = cleric_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(2, 2), p1);
+        game.spawn_unit(class, Position::new(6, 6), p2);
+        let unit = game.get_unit_mut(UnitId(0)).unwrap();
+        unit.health = 4;
+        unit.effects.push(Effect::Regeneration { amount: 2, turns: 1 });
+        game.commit_turn();
+
+        assert_eq!(game.get_unit(UnitId(0)).unwrap().health, 6);
+        assert!(game.get_unit(UnitId(0)).unwrap().effects.is_empty());
+        assert!(matches!(game.current_changes(), [ResolvedChange::Regeneration { .. }]));
+    }
+}
@@ -1,3 +1,19 @@
 pub mod state;
+pub mod victory;
+pub mod targeting;
+pub mod resolution;
+pub mod reaction;
+pub mod movement;
+pub mod ability;
+pub mod cooldowns;
+pub mod healing;
+
+#[cfg(test)]
+mod testing;
 
 pub use state::Game;
+pub use ability::AbilityResolution;
+pub use healing::{Heal, ShieldChange};
+pub use victory::{GameOutcome, VictoryCondition};
+pub use targeting::Target;
+pub use resolution::{AttackForecast, AttackResolution, AttackRoll, DisplacementResult, Hit, HitForecast, HitOutcome, Rolls, Side};
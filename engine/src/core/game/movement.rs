@@ -0,0 +1,296 @@
+//! Voluntary movement of the unit whose turn it is.
+//!
+//! Validation, pathfinding and reachable tiles all use the same `MoveRules`,
+//! so a path the UI offers is always a path the server accepts.
+
+use std::mem;
+
+use crate::core::geom::{Path, Position};
+use crate::core::grid::pathfinding::{self, MoveRules};
+use crate::core::player::PlayerId;
+use crate::core::unit::UnitId;
+
+use super::state::GameError;
+use super::Game;
+
+impl Game {
+    /// Rules `unit` moves under: other units block, and tiles next to its
+    /// enemies form their zone of control
+    pub fn move_rules(&self, unit: UnitId) -> MoveRules {
+        let Some(mover) = self.get_unit(unit) else { return MoveRules::default() };
+
+        let mut rules = MoveRules {
+            zone_of_control: self.registry().get(mover.class).map(|d| d.zone_of_control).unwrap_or_default(),
+            ..MoveRules::default()
+        };
+        for other in self.units_iter().filter(|u| u.id != unit) {
+            rules.blocked.insert(other.get_pos());
+
+            if self.are_enemies(mover.owner, other.owner) {
+                rules.zone.extend(self.grid().neighbors(other.get_pos()));
+            }
+        }
+        rules
+    }
+
+    /// Movement points `unit` has left this turn: its speed, less what it
+    /// already walked if it's the one moving now, none once a zone of control stopped it
+    pub fn move_budget(&self, unit: UnitId) -> u32 {
+        let speed = self.get_unit(unit)
+            .and_then(|u| self.registry().get(u.class))
+            .map_or(0, |d| d.base_speed as u32);
+
+        if self.current_unit() != Some(unit) {
+            speed
+        } else if self.stopped_in_zone() {
+            0
+        } else {
+            speed.saturating_sub(self.movement_spent())
+        }
+    }
+
+    /// Tiles `unit` could move to this turn, sorted, its own excluded
+    pub fn reachable_tiles(&self, unit: UnitId) -> Vec<Position> {
+        let Some(start) = self.get_unit(unit).map(|u| u.get_pos()) else { return Vec::new() };
+
+        let mut tiles: Vec<Position> = pathfinding::reachable(self.grid(), start, self.move_budget(unit), &self.move_rules(unit))
+            .into_keys()
+            .filter(|&pos| pos != start)
+            .collect();
+        tiles.sort_by_key(|p| (p.y(), p.x()));
+        tiles
+    }
+
+    /// Cheapest legal path for `unit` to `goal`, if it fits this turn
+    pub fn path_to(&self, unit: UnitId, goal: Position) -> Option<Path> {
+        let start = self.get_unit(unit)?.get_pos();
+        let rules = self.move_rules(unit);
+
+        let path = pathfinding::find_path_with(self.grid(), start, goal, &rules)?;
+        let cost: u32 = path_tiles(&path).map(|pos| rules.step_cost(self.grid(), pos)).sum();
+
+        (cost <= self.move_budget(unit)).then_some(path)
+    }
+
+    /// Check a proposed move by `player` with the unit whose turn it is.
+    /// Every step must be walkable and free, movement must end at the first
+    /// enemy zone of control entered (unless the class ignores it), and the
+    /// cost of the whole path must fit in what is left of the unit's speed.
+    /// A unit may leave a zone it starts the turn in, not one it walked into.
+    pub fn validate_move(&self, player: PlayerId, path: &Path) -> Result<(), GameError> {
+        let mover = self.current_unit().ok_or(GameError::WrongPhase)?;
+        let unit = self.get_unit(mover).ok_or(GameError::InvalidUnit)?;
+        if unit.owner != player {
+            return Err(GameError::NotYourTurn);
+        }
+        if path.start() != unit.get_pos() {
+            return Err(GameError::InvalidPath);
+        }
+        if self.stopped_in_zone() {
+            return Err(GameError::ZoneOfControl);
+        }
+
+        let grid = self.grid();
+        let rules = self.move_rules(mover);
+        let mut cost = 0u32;
+        let mut pos = path.start();
+
+        for dir in path {
+            if pos != path.start() && rules.stops_at(pos) {
+                return Err(GameError::ZoneOfControl);
+            }
+
+            let next = pos.offset(dir.dir_vec());
+            if !grid.can_step(pos, next) {
+                return Err(GameError::InvalidPath);
+            }
+            if rules.blocked.contains(next) {
+                return Err(GameError::TileOccupied);
+            }
+
+            cost = cost.saturating_add(rules.step_cost(grid, next));
+            pos = next;
+        }
+        let budget = self.move_budget(mover);
+
+        if pos != path.end() {
+            return Err(GameError::InvalidPath);
+        }
+        if cost > budget {
+            return Err(GameError::OutOfRange);
+        }
+
+        Ok(())
+    }
+
+    /// Walk `mover` along an already validated path one step at a time,
+    /// opening a reaction window before each step. The move is logged in
+    /// pieces around reactions and stops early if the mover dies or is displaced.
+    pub(super) fn perform_move(&mut self, mover: UnitId, path: Path) {
+        let rules = self.move_rules(mover);
+        let mut start = path.start();
+        let mut pos = start;
+        let mut steps = Vec::new();
+
+        for &dir in &path {
+            let next = pos.offset(dir.dir_vec());
+
+            let reactions = self.opportunity_reactions(mover, pos, next);
+            if !reactions.is_empty() {
+                // Reactions hit the mover where it stands
+                if !steps.is_empty() {
+                    self.move_unit(mover, Path::new(mem::take(&mut steps), start, pos));
+                    start = pos;
+                }
+
+                self.perform_reactions(&reactions, mover);
+                if self.get_unit(mover).is_none_or(|u| u.get_pos() != pos) {
+                    return;
+                }
+            }
+
+            steps.push(dir);
+            pos = next;
+            self.spend_movement(rules.step_cost(self.grid(), next), rules.stops_at(next));
+        }
+
+        if !steps.is_empty() {
+            self.move_unit(mover, Path::new(steps, start, pos));
+        }
+    }
+}
+
+/// Tiles entered along `path`, start excluded
+fn path_tiles(path: &Path) -> impl Iterator<Item = Position> + '_ {
+    path.into_iter().scan(path.start(), |pos, dir| {
+        *pos = pos.offset(dir.dir_vec());
+        Some(*pos)
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::game::state::ProposedAction;
+    use crate::core::game::testing::{self, unit_class};
+    use crate::core::geom::Direction;
+    use crate::core::grid::{Grid, TerrainType};
+    use crate::core::unit::UnitClassId;
+
+    /// Walkers have speed 3 and stop in enemy zones, (3, 4) is water
+    fn walker_game() -> Game {
+        let mut grid = Grid::new(8, 8);
+        grid.set_terrain(Position::new(3, 4), TerrainType::WaterStill).unwrap();
+        testing::game().class(unit_class("Walker")).grid(grid).build()
+    }
+
+    #[test]
+    fn test_validate_move() {
+        let mut game = walker_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(2, 2), p1);
+        game.spawn_unit(class, Position::new(3, 2), p1);
+        game.spawn_unit(class, Position::new(7, 7), p2);
+        game.commit_turn();
+
+        let from = Position::new(2, 2);
+        let right = Path::new(vec![Direction::Right], from, Position::new(3, 2));
+        let up = Path::new(vec![Direction::UpRight, Direction::UpRight], from, Position::new(2, 4));
+        let wet = Path::new(vec![Direction::UpRight, Direction::UpRight, Direction::Right], from, Position::new(3, 4));
+        let lying = Path::new(vec![Direction::UpRight], from, Position::new(5, 5));
+
+        assert!(matches!(game.validate_move(p1, &right), Err(GameError::TileOccupied)));
+        assert!(matches!(game.validate_move(p1, &wet), Err(GameError::OutOfRange)));
+        assert!(matches!(game.validate_move(p1, &lying), Err(GameError::InvalidPath)));
+        assert!(matches!(game.validate_move(p2, &up), Err(GameError::NotYourTurn)));
+
+        game.handle_action(p1, ProposedAction::Move { path: up }).unwrap();
+        assert_eq!(game.get_unit(UnitId(0)).unwrap().get_pos(), Position::new(2, 4));
+        assert_eq!(game.grid().get_occupancy(Position::new(2, 4)), Some(UnitId(0)));
+    }
+
+    #[test]
+    fn test_zone_of_control_limits_moves() {
+        let mut game = walker_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        // A corridor along y = 1 with an enemy standing beside it
+        for y in 0..8 {
+            for x in 0..8 {
+                if y != 1 && (x, y) != (2, 2) {
+                    game.grid_mut().set_terrain(Position::new(x, y), TerrainType::Void).unwrap();
+                }
+            }
+        }
+        game.spawn_unit(class, Position::new(0, 1), p1);
+        game.spawn_unit(class, Position::new(2, 2), p2);
+        game.commit_turn();
+
+        // (2, 1) is next to the enemy: reachable, but a dead end
+        let through = Path::new(vec![Direction::Right, Direction::Right], Position::new(0, 1), Position::new(2, 1));
+        assert!(game.validate_move(p1, &through).is_ok());
+        let past = Path::new(vec![Direction::Right; 3], Position::new(0, 1), Position::new(3, 1));
+        assert!(matches!(game.validate_move(p1, &past), Err(GameError::ZoneOfControl)));
+
+        assert_eq!(game.reachable_tiles(UnitId(0)), vec![Position::new(1, 1), Position::new(2, 1)]);
+        assert!(game.path_to(UnitId(0), Position::new(3, 1)).is_none());
+        assert!(game.path_to(UnitId(0), Position::new(2, 1)).is_some());
+
+        // Stopping there can't be dodged by moving in two goes
+        game.handle_action(p1, ProposedAction::Move { path: through }).unwrap();
+        assert!(game.stopped_in_zone());
+        let on = Path::new(vec![Direction::Right], Position::new(2, 1), Position::new(3, 1));
+        assert!(matches!(game.validate_move(p1, &on), Err(GameError::ZoneOfControl)));
+        assert!(game.reachable_tiles(UnitId(0)).is_empty());
+    }
+
+    #[test]
+    fn test_moves_share_the_budget() {
+        let mut game = walker_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(0, 0), p1);
+        game.spawn_unit(class, Position::new(7, 7), p2);
+        game.commit_turn();
+
+        let first = Path::new(vec![Direction::Right; 2], Position::new(0, 0), Position::new(2, 0));
+        game.handle_action(p1, ProposedAction::Move { path: first }).unwrap();
+        assert_eq!((game.movement_spent(), game.move_budget(UnitId(0))), (2, 1));
+
+        let too_far = Path::new(vec![Direction::Right; 2], Position::new(2, 0), Position::new(4, 0));
+        assert!(matches!(game.validate_move(p1, &too_far), Err(GameError::OutOfRange)));
+        let last = Path::new(vec![Direction::Right], Position::new(2, 0), Position::new(3, 0));
+        game.handle_action(p1, ProposedAction::Move { path: last }).unwrap();
+        assert!(game.reachable_tiles(UnitId(0)).is_empty());
+
+        // The next turn starts with full speed again
+        game.handle_action(p1, ProposedAction::EndTurn).unwrap();
+        assert_eq!(game.movement_spent(), 0);
+        assert_eq!(game.current_unit(), Some(UnitId(1)));
+        assert_eq!(game.move_budget(UnitId(1)), 3);
+    }
+
+    #[test]
+    fn test_facing_follows_moves_and_turns() {
+        let mut game = walker_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(2, 2), p1);
+        game.spawn_unit(class, Position::new(7, 7), p2);
+        game.commit_turn();
+
+        let path = Path::new(vec![Direction::Right, Direction::UpRight], Position::new(2, 2), Position::new(3, 3));
+        game.handle_action(p1, ProposedAction::Move { path }).unwrap();
+        assert_eq!(game.get_unit(UnitId(0)).unwrap().facing, Direction::UpRight);
+
+        game.handle_action(p1, ProposedAction::Turn { facing: Direction::Left }).unwrap();
+        assert_eq!(game.get_unit(UnitId(0)).unwrap().facing, Direction::Left);
+        assert!(matches!(game.handle_action(p2, ProposedAction::Turn { facing: Direction::Left }), Err(GameError::NotYourTurn)));
+    }
+}
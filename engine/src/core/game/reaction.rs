@@ -0,0 +1,252 @@
+//! Reactions: attacks units make outside their own turn.
+//!
+//! Reactions resolve inside the action that triggered them, right after the
+//! step that did. The attacked units counter first (primary target first),
+//! then guards, lowest id first; opportunity attacks fire as the mover leaves
+//! each tile, lowest id first. Each unit reacts at most once per round and
+//! reactions never trigger further reactions.
+
+use crate::core::combat::{ActionKey, AttackId, ReactionTrigger};
+use crate::core::geom::Position;
+use crate::core::unit::UnitId;
+
+use super::resolution::{Board, Hit, HitOutcome};
+use super::Game;
+
+impl Game {
+    /// The attack `reactor` would answer `trigger` with against `enemy` right now
+    pub fn available_reaction(&self, reactor: UnitId, trigger: ReactionTrigger, enemy: UnitId) -> Option<AttackId> {
+        self.reaction_on(self.board(), reactor, trigger, enemy)
+    }
+
+    /// `available_reaction` with the units standing as on `board`
+    pub(super) fn reaction_on(&self, board: Board, reactor: UnitId, trigger: ReactionTrigger, enemy: UnitId) -> Option<AttackId> {
+        if self.has_reacted(reactor) {
+            return None;
+        }
+        let unit = board.unit(reactor)?;
+        let enemy = board.unit(enemy)?;
+        if unit.health == 0 || enemy.health == 0 || !self.are_enemies(unit.owner, enemy.owner) {
+            return None;
+        }
+
+        self.registry()
+            .get(unit.class)?
+            .reactions
+            .iter()
+            .filter(|reaction| reaction.trigger == trigger)
+            .filter(|reaction| self.check_usage(unit, ActionKey::Attack(reaction.attack)).is_ok())
+            .find(|reaction| {
+                self.registry()
+                    .attack(unit.class, reaction.attack)
+                    .is_some_and(|attack| self.check_target(unit, attack, enemy).is_ok())
+            })
+            .map(|reaction| reaction.attack)
+    }
+
+    /// Units reacting to `attacker` landing `hits`, in resolution order.
+    /// Must be called before the dead are removed so guards still find them.
+    pub(super) fn attack_reactions(&self, attacker: UnitId, hits: &[Hit]) -> Vec<(UnitId, ReactionTrigger)> {
+        let Some(owner) = self.get_unit(attacker).map(|u| u.owner) else { return Vec::new() };
+
+        let victims: Vec<&Hit> = hits.iter()
+            .filter(|hit| self.get_unit(hit.unit).is_some_and(|u| self.are_enemies(owner, u.owner)))
+            .collect();
+
+        let mut reactions: Vec<(UnitId, ReactionTrigger)> = victims.iter()
+            .filter(|hit| self.available_reaction(hit.unit, ReactionTrigger::Attacked, attacker).is_some())
+            .map(|hit| (hit.unit, ReactionTrigger::Attacked))
+            .collect();
+
+        let mut guards: Vec<UnitId> = victims.iter()
+            .filter(|hit| hit.roll.outcome != HitOutcome::Miss)
+            .filter_map(|hit| self.get_unit(hit.unit))
+            .flat_map(|victim| {
+                self.adjacent_units(victim.get_pos())
+                    .into_iter()
+                    .filter(move |&id| self.get_unit(id).is_some_and(|u| self.are_allies(u.owner, victim.owner)))
+            })
+            .filter(|&id| self.available_reaction(id, ReactionTrigger::AllyHit, attacker).is_some())
+            .collect();
+        guards.sort();
+        guards.dedup();
+
+        for guard in guards {
+            if !reactions.iter().any(|&(id, _)| id == guard) {
+                reactions.push((guard, ReactionTrigger::AllyHit));
+            }
+        }
+        reactions
+    }
+
+    /// Enemies of `mover` that get an opportunity attack as it steps from `from` to `to`
+    pub(super) fn opportunity_reactions(&self, mover: UnitId, from: Position, to: Position) -> Vec<(UnitId, ReactionTrigger)> {
+        let mut reactors: Vec<UnitId> = self.adjacent_units(from)
+            .into_iter()
+            .filter(|&id| self.get_unit(id).is_some_and(|u| u.get_pos().distance(to) > 1))
+            .filter(|&id| self.available_reaction(id, ReactionTrigger::EnemyLeaves, mover).is_some())
+            .collect();
+        reactors.sort();
+
+        reactors.into_iter().map(|id| (id, ReactionTrigger::EnemyLeaves)).collect()
+    }
+
+    /// Resolve `reactions` against `enemy`, skipping any that stopped being
+    /// possible because of an earlier one
+    pub(super) fn perform_reactions(&mut self, reactions: &[(UnitId, ReactionTrigger)], enemy: UnitId) {
+        for &(reactor, trigger) in reactions {
+            let Some(attack) = self.available_reaction(reactor, trigger, enemy) else { continue };
+            let Some(target_pos) = self.get_unit(enemy).map(|u| u.get_pos()) else { return };
+
+            self.mark_reacted(reactor);
+            self.execute_attack(reactor, attack, target_pos, Some(trigger))
+                .expect("available_reaction checked the attack against the enemy where it stands");
+        }
+        self.remove_dead_units();
+    }
+
+    fn adjacent_units(&self, pos: Position) -> Vec<UnitId> {
+        self.grid()
+            .neighbors(pos)
+            .filter_map(|next| self.grid().get_occupancy(next))
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::combat::{AttackDefinition, AttackRange, Displacement, ReactionDefinition};
+    use crate::core::geom::{Direction, Path};
+    use crate::core::game::state::{ProposedAction, ResolvedChange};
+    use crate::core::game::testing::{self, attack, unit_class};
+    use crate::core::grid::ZoneOfControl;
+    use crate::core::player::PlayerId;
+    use crate::core::unit::{UnitClassId, UnitDefinition};
+
+    /// Soldiers counter and guard, sentries slow and take opportunity attacks,
+    /// brutes push (attack 0) and pull from afar (attack 1) but never react
+    fn reaction_game() -> Game {
+        let class = |name: &str, speed, reactions| UnitDefinition {
+            attacks: vec![attack()],
+            reactions,
+            base_speed: speed,
+            zone_of_control: ZoneOfControl::Ignore,  // free to walk around enemies
+            ..unit_class(name)
+        };
+        let react = |trigger| ReactionDefinition { trigger, attack: AttackId::new(0) };
+
+        testing::game()
+            .class(class("Soldier", 5, vec![react(ReactionTrigger::Attacked), react(ReactionTrigger::AllyHit)]))
+            .class(class("Sentry", 1, vec![react(ReactionTrigger::EnemyLeaves)]))
+            .class(UnitDefinition {
+                attacks: vec![
+                    AttackDefinition { displacement: Some(Displacement::Push(1)), ..attack() },
+                    AttackDefinition {
+                        range: AttackRange { inner_radius: 1, outer_radius: 2 },
+                        displacement: Some(Displacement::Pull(1)),
+                        ..attack()
+                    },
+                ],
+                ..class("Brute", 5, Vec::new())
+            })
+            .build()
+    }
+
+    fn reactions_logged(game: &Game) -> Vec<(UnitId, ReactionTrigger)> {
+        reactions_in(game.current_changes())
+    }
+
+    fn reactions_in(changes: &[ResolvedChange]) -> Vec<(UnitId, ReactionTrigger)> {
+        changes
+            .iter()
+            .filter_map(|c| match c {
+                ResolvedChange::Reaction { unit, trigger, .. } => Some((*unit, *trigger)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_counter_then_guard() {
+        let mut game = reaction_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let soldier = UnitClassId::new(0);
+
+        game.spawn_unit(soldier, Position::new(2, 2), p1);  // attacker
+        game.spawn_unit(soldier, Position::new(3, 2), p2);  // target
+        game.spawn_unit(soldier, Position::new(2, 3), p2);  // next to both, guards
+        game.commit_turn();
+
+        let forecast = game.forecast(UnitId(0), AttackId::new(0), Position::new(3, 2)).unwrap();
+        assert_eq!(forecast.counterattack.unwrap().hits[0].expected_damage, 3.0);
+
+        game.handle_action(p1, ProposedAction::Attack { target: UnitId(1), attack: AttackId::new(0) }).unwrap();
+
+        assert_eq!(reactions_logged(&game), vec![
+            (UnitId(1), ReactionTrigger::Attacked),
+            (UnitId(2), ReactionTrigger::AllyHit),
+        ]);
+        assert_eq!(game.get_unit(UnitId(0)).unwrap().health, 4);
+        assert!(game.has_reacted(UnitId(1)));
+    }
+
+    #[test]
+    fn test_counter_after_knockback() {
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let (soldier, brute) = (UnitClassId::new(0), UnitClassId::new(2));
+        let attack = |target: UnitId, attack: AttackId| ProposedAction::Attack { target, attack };
+
+        // Pushed out of sword reach, the soldier can't counter
+        let mut game = reaction_game();
+        game.spawn_unit(brute, Position::new(2, 2), p1);
+        game.spawn_unit(soldier, Position::new(3, 2), p2);
+        game.commit_turn();
+
+        let forecast = game.forecast(UnitId(0), AttackId::new(0), Position::new(3, 2)).unwrap();
+        assert!(forecast.counterattack.is_none());
+        game.handle_action(p1, attack(UnitId(1), AttackId::new(0))).unwrap();
+        assert_eq!(game.get_unit(UnitId(1)).unwrap().get_pos(), Position::new(4, 2));
+        assert!(reactions_logged(&game).is_empty());
+
+        // Pulled within reach, it can
+        let mut game = reaction_game();
+        game.spawn_unit(brute, Position::new(2, 2), p1);
+        game.spawn_unit(soldier, Position::new(2, 4), p2);
+        game.commit_turn();
+
+        let forecast = game.forecast(UnitId(0), AttackId::new(1), Position::new(2, 4)).unwrap();
+        assert_eq!(forecast.counterattack.unwrap().hits[0].expected_damage, 3.0);
+        game.handle_action(p1, attack(UnitId(1), AttackId::new(1))).unwrap();
+        assert_eq!(game.get_unit(UnitId(1)).unwrap().get_pos(), Position::new(2, 3));
+        assert_eq!(reactions_logged(&game), vec![(UnitId(1), ReactionTrigger::Attacked)]);
+        assert_eq!(game.get_unit(UnitId(0)).unwrap().health, 7);
+    }
+
+    #[test]
+    fn test_opportunity_attack_interrupts_move() {
+        let mut game = reaction_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let (soldier, sentry) = (UnitClassId::new(0), UnitClassId::new(1));
+
+        game.spawn_unit(soldier, Position::new(2, 2), p1);
+        game.spawn_unit(sentry, Position::new(3, 2), p2);
+        game.get_unit_mut(UnitId(0)).unwrap().health = 2;
+        game.commit_turn();
+
+        // Walking around the sentry is free, leaving it is not
+        let path = Path::new(vec![Direction::UpRight, Direction::UpLeft], Position::new(2, 2), Position::new(1, 4));
+        game.handle_action(p1, ProposedAction::Move { path }).unwrap();
+
+        assert_eq!(reactions_in(game.previous_changes()), vec![(UnitId(1), ReactionTrigger::EnemyLeaves)]);
+        // The mover's turn ended with it
+        assert!(game.get_unit(UnitId(0)).is_none());
+        assert_eq!(game.current_unit(), Some(UnitId(1)));
+        let moved: Vec<&ResolvedChange> = game.previous_changes()
+            .iter()
+            .filter(|c| matches!(c, ResolvedChange::Move { .. }))
+            .collect();
+        assert_eq!(moved.len(), 1);  // only the step taken before dying
+    }
+}
@@ -0,0 +1,895 @@
+//! Attack resolution.
+//!
+//! `resolve_attack` computes everything an attack does without touching the
+//! game. Real attacks apply its result and forecasts only read it, so a
+//! preview always matches what happens.
+//!
+//! Hit and crit rolls come from the game's seeded `Rng` and are stored in
+//! each `Hit`, so replays and clients reproduce them exactly.
+
+use crate::core::combat::{ActionKey, AoePattern, Armor, AttackDefinition, AttackId, Displacement, Effect, ReactionTrigger};
+use crate::core::geom::line::line_steps;
+use crate::core::geom::{shapes, Direction, HexSet, Position};
+use crate::core::grid::{grid::MAX_CLIMB, TerrainType};
+use crate::core::rng::Rng;
+use crate::core::unit::{Unit, UnitId};
+
+use super::state::{GameError, ResolvedChange};
+use super::Game;
+
+/// Damage taken by a displaced unit and by whatever it slams into
+const COLLISION_DAMAGE: u32 = 2;
+
+/// Damage multipliers for attacks from a unit's flank and back, see `Side`
+pub const FLANK_MULTIPLIER: f32 = 1.25;
+pub const REAR_MULTIPLIER: f32 = 1.5;
+
+/// Where an attack comes from relative to the target's facing: the three
+/// tiles it faces are its front, the two behind those its flanks, the one
+/// straight behind its rear
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Front,
+    Flank,
+    Rear,
+}
+
+impl Side {
+    pub fn multiplier(&self) -> f32 {
+        match self {
+            Side::Front => 1.0,
+            Side::Flank => FLANK_MULTIPLIER,
+            Side::Rear => REAR_MULTIPLIER,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitOutcome {
+    Miss,
+    Hit,
+    Crit,
+}
+
+impl HitOutcome {
+    pub const ALL: [HitOutcome; 3] = [HitOutcome::Miss, HitOutcome::Hit, HitOutcome::Crit];
+}
+
+/// Rolls behind a hit. It lands if `hit_roll < hit_chance`
+/// and crits if it lands and `crit_roll < crit_chance`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AttackRoll {
+    pub outcome: HitOutcome,
+    pub hit_roll: f32,
+    pub crit_roll: f32,
+    pub hit_chance: f32,  // after evasion
+    pub crit_chance: f32,
+}
+
+/// Where hit and crit rolls come from
+pub enum Rolls<'a> {
+    Random(&'a mut Rng),
+    Fixed(HitOutcome),  // every unit gets this outcome, for forecasts
+}
+
+/// What an attack does to one unit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub unit: UnitId,
+    pub roll: AttackRoll,
+    pub damage: u32,
+    pub absorbed: u32,  // part of `damage` taken by the shield
+    pub health_before: u32,
+    pub health_after: u32,
+    pub effects: Vec<Effect>,
+}
+
+impl Hit {
+    /// `damage` landing on a unit with `health` and `shield`, the shield soaking first
+    pub(super) fn new(unit: UnitId, roll: AttackRoll, damage: u32, health: u32, shield: u32, effects: Vec<Effect>) -> Self {
+        let absorbed = damage.min(shield);
+
+        Hit {
+            unit,
+            roll,
+            damage,
+            absorbed,
+            health_before: health,
+            health_after: health.saturating_sub(damage - absorbed),
+            effects,
+        }
+    }
+
+    pub fn is_lethal(&self) -> bool {
+        self.health_after == 0
+    }
+}
+
+/// Where a displaced unit ends up and what it cost
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplacementResult {
+    pub unit: UnitId,
+    pub kind: Displacement,
+    pub from: Position,
+    pub to: Position,
+    pub swapped_with: Option<UnitId>,  // the attacker, for swaps
+    pub collision_hits: Vec<Hit>,  // the displaced unit and what it hit
+    pub fell: bool,  // pushed into the void, lethal
+}
+
+/// The units as an attack sees them: the game's, except for `moved`, which
+/// stand where they are instead. Lets a forecast look past a displacement
+/// without changing the game.
+#[derive(Clone, Copy)]
+pub(super) struct Board<'a> {
+    game: &'a Game,
+    moved: &'a [Unit],
+}
+
+impl<'a> Board<'a> {
+    pub(super) fn new(game: &'a Game, moved: &'a [Unit]) -> Self {
+        Board { game, moved }
+    }
+
+    pub(super) fn unit(&self, id: UnitId) -> Option<&'a Unit> {
+        self.moved.iter().find(|u| u.id == id).or_else(|| self.game.get_unit(id))
+    }
+
+    /// The unit standing on `pos`
+    pub(super) fn occupant(&self, pos: Position) -> Option<&'a Unit> {
+        self.moved.iter().find(|u| u.get_pos() == pos).or_else(|| {
+            self.game.grid()
+                .get_occupancy(pos)
+                .and_then(|id| self.unit(id))
+                .filter(|u| u.get_pos() == pos)  // unless it was moved away
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttackResolution {
+    pub attacker: UnitId,
+    pub attack: AttackId,
+    pub target: Position,
+    pub hits: Vec<Hit>,  // primary target first
+    pub displacement: Option<DisplacementResult>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HitForecast {
+    pub unit: UnitId,
+    pub expected_damage: f32,
+    pub kill_chance: f32,
+    pub effects: Vec<Effect>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttackForecast {
+    pub hits: Vec<HitForecast>,
+    pub counterattack: Option<Box<AttackForecast>>,
+}
+
+impl AttackRoll {
+    fn roll(rolls: &mut Rolls, hit_chance: f32, crit_chance: f32) -> Self {
+        let (outcome, hit_roll, crit_roll) = match rolls {
+            Rolls::Random(rng) => {
+                // Always draw both so the sequence doesn't depend on outcomes
+                let hit_roll = rng.next_f32();
+                let crit_roll = rng.next_f32();
+
+                let outcome = if hit_roll >= hit_chance {
+                    HitOutcome::Miss
+                } else if crit_roll < crit_chance {
+                    HitOutcome::Crit
+                } else {
+                    HitOutcome::Hit
+                };
+                (outcome, hit_roll, crit_roll)
+            }
+            Rolls::Fixed(outcome) => (*outcome, 0.0, 0.0),
+        };
+
+        AttackRoll { outcome, hit_roll, crit_roll, hit_chance, crit_chance }
+    }
+
+    /// For damage that can't miss, e.g. collisions
+    pub(super) fn certain() -> Self {
+        AttackRoll {
+            outcome: HitOutcome::Hit,
+            hit_roll: 0.0,
+            crit_roll: 0.0,
+            hit_chance: 1.0,
+            crit_chance: 0.0,
+        }
+    }
+
+    /// Probability of `outcome` given the chances
+    fn probability(&self, outcome: HitOutcome) -> f32 {
+        match outcome {
+            HitOutcome::Miss => 1.0 - self.hit_chance,
+            HitOutcome::Hit => self.hit_chance * (1.0 - self.crit_chance),
+            HitOutcome::Crit => self.hit_chance * self.crit_chance,
+        }
+    }
+}
+
+impl Game {
+    /// Preview of `attacker` using `attack` on the unit at `target_pos`.
+    /// Every outcome is resolved with the real code and weighted by its chance.
+    /// The counterattack is previewed as if the attack lands. Does not change the game.
+    pub fn forecast(&self, attacker: UnitId, attack: AttackId, target_pos: Position) -> Result<AttackForecast, GameError> {
+        let mut forecast = self.forecast_hits(self.board(), attacker, attack, target_pos)?;
+
+        // Counters resolve after the displacement, from wherever the target ends up
+        let landed = self.resolve_attack(attacker, attack, target_pos, &mut Rolls::Fixed(HitOutcome::Hit))?;
+        let moved = landed.displacement.map_or(Vec::new(), |d| self.displaced_units(&d));
+        let board = Board::new(self, &moved);
+
+        // Only if the target lives
+        let target = forecast.hits[0].unit;
+        if forecast.hits[0].kill_chance < 1.0
+            && let Some(counter) = self.reaction_on(board, target, ReactionTrigger::Attacked, attacker)
+        {
+            let attacker_pos = board.unit(attacker).map(|u| u.get_pos()).ok_or(GameError::InvalidUnit)?;
+            forecast.counterattack = Some(Box::new(self.forecast_hits(board, target, counter, attacker_pos)?));
+        }
+
+        Ok(forecast)
+    }
+
+    /// `forecast` without reactions
+    fn forecast_hits(&self, board: Board, attacker: UnitId, attack: AttackId, target_pos: Position) -> Result<AttackForecast, GameError> {
+        let mut outcomes = Vec::new();
+        for outcome in HitOutcome::ALL {
+            outcomes.push(self.resolve_attack_on(board, attacker, attack, target_pos, &mut Rolls::Fixed(outcome))?);
+        }
+
+        let mut hits: Vec<HitForecast> = Vec::new();
+        let mut add = |unit: UnitId, p: f32, damage: u32, lethal: bool| {
+            let index = hits.iter().position(|h| h.unit == unit).unwrap_or_else(|| {
+                hits.push(HitForecast { unit, expected_damage: 0.0, kill_chance: 0.0, effects: Vec::new() });
+                hits.len() - 1
+            });
+            hits[index].expected_damage += p * damage as f32;
+            if lethal {
+                hits[index].kill_chance = (hits[index].kill_chance + p).min(1.0);
+            }
+        };
+
+        for resolution in &outcomes {
+            for hit in &resolution.hits {
+                add(hit.unit, hit.roll.probability(hit.roll.outcome), hit.damage, hit.is_lethal());
+            }
+
+            // Displacement hinges on the roll against the picked target
+            if let Some(displaced) = &resolution.displacement {
+                let primary = &resolution.hits[0].roll;
+                let p = primary.probability(primary.outcome);
+
+                for hit in &displaced.collision_hits {
+                    add(hit.unit, p, hit.damage, hit.is_lethal());
+                }
+                if displaced.fell {
+                    add(displaced.unit, p, 0, true);
+                }
+            }
+        }
+
+        for hit in &outcomes[1].hits {
+            if let Some(forecast) = hits.iter_mut().find(|h| h.unit == hit.unit)
+                && hit.roll.hit_chance > 0.0
+            {
+                forecast.effects = hit.effects.clone();
+            }
+        }
+
+        Ok(AttackForecast {
+            hits,
+            counterattack: None,
+        })
+    }
+
+    /// Everything `attacker` using `attack` on the unit at `target_pos` would do
+    pub fn resolve_attack(&self, attacker: UnitId, attack: AttackId, target_pos: Position, rolls: &mut Rolls) -> Result<AttackResolution, GameError> {
+        self.resolve_attack_on(self.board(), attacker, attack, target_pos, rolls)
+    }
+
+    fn resolve_attack_on(&self, board: Board, attacker: UnitId, attack: AttackId, target_pos: Position, rolls: &mut Rolls) -> Result<AttackResolution, GameError> {
+        let unit = board.unit(attacker).ok_or(GameError::InvalidUnit)?;
+        let definition = self.registry()
+            .attack(unit.class, attack)
+            .ok_or(GameError::UnknownAttack)?;
+        let target = board.occupant(target_pos).ok_or(GameError::InvalidTarget)?;
+
+        self.check_target(unit, definition, target)?;
+
+        let hits: Vec<Hit> = aoe_positions(unit.get_pos(), unit.facing, target_pos, definition.aoe)
+            .into_iter()
+            .filter_map(|pos| board.occupant(pos))
+            .filter(|victim| victim.id == target.id || self.splash_affects(unit, definition, victim))
+            .map(|victim| self.compute_hit(definition, unit.get_pos(), victim, rolls))
+            .collect();
+
+        // Only a target that was hit and survived gets moved
+        let displacement = match definition.displacement {
+            Some(kind) if hits[0].roll.outcome != HitOutcome::Miss && !hits[0].is_lethal() => {
+                Some(self.resolve_displacement(board, unit, target, &hits, kind))
+            }
+            _ => None,
+        };
+
+        Ok(AttackResolution {
+            attacker,
+            attack,
+            target: target_pos,
+            hits,
+            displacement,
+        })
+    }
+
+    /// Resolve an attack for real: apply it, log it, clear the dead and
+    /// let the units it provoked react
+    pub(super) fn perform_attack(&mut self, attacker: UnitId, attack: AttackId, target_pos: Position) -> Result<(), GameError> {
+        let hits = self.execute_attack(attacker, attack, target_pos, None)?;
+
+        let reactions = self.attack_reactions(attacker, &hits);
+        self.remove_dead_units();
+        self.perform_reactions(&reactions, attacker);
+
+        Ok(())
+    }
+
+    /// Roll, apply and log an attack, as a reaction to `trigger` if given.
+    /// Returns the hits, the dead are left in place.
+    pub(super) fn execute_attack(&mut self, attacker: UnitId, attack: AttackId, target_pos: Position, trigger: Option<ReactionTrigger>) -> Result<Vec<Hit>, GameError> {
+        let mut rng = self.rng().clone();
+        let resolution = self.resolve_attack(attacker, attack, target_pos, &mut Rolls::Random(&mut rng))?;
+        self.set_rng(rng);
+
+        let target = resolution.hits[0].unit;
+        let hits = resolution.hits;
+
+        self.record_usage(attacker, ActionKey::Attack(attack));
+        self.apply_hits(&hits);
+        self.log_change(match trigger {
+            None => ResolvedChange::Attack { attacker, target, attack, hits: hits.clone() },
+            Some(trigger) => ResolvedChange::Reaction { unit: attacker, trigger, target, attack, hits: hits.clone() },
+        });
+
+        if let Some(displacement) = resolution.displacement {
+            self.apply_displacement(&displacement);
+            self.log_change(ResolvedChange::Displaced { displacement });
+        }
+
+        Ok(hits)
+    }
+
+    pub(super) fn apply_hits(&mut self, hits: &[Hit]) {
+        for hit in hits {
+            if let Some(unit) = self.get_unit_mut(hit.unit) {
+                unit.health = hit.health_after;
+                unit.shield = unit.shield.saturating_sub(hit.absorbed);
+                unit.effects.extend(hit.effects.iter().copied());
+            }
+        }
+    }
+
+    pub(super) fn apply_displacement(&mut self, displaced: &DisplacementResult) {
+        let (from, to) = (displaced.from, displaced.to);
+
+        if let Some(other) = displaced.swapped_with {
+            // Free the target's tile first so the attacker doesn't overwrite it
+            let grid = self.grid_mut();
+            grid.set_occupancy(from, None)
+                .and_then(|_| grid.move_occupancy(to, from))
+                .and_then(|_| grid.set_occupancy(to, Some(displaced.unit)))
+                .expect("swapped units stand on the map");
+
+            if let Some(unit) = self.get_unit_mut(other) {
+                unit.change_pos(from);
+            }
+        } else if from != to {
+            self.grid_mut().move_occupancy(from, to).expect("displacement stays on the map");
+        }
+
+        if let Some(unit) = self.get_unit_mut(displaced.unit) {
+            unit.change_pos(to);
+            if displaced.fell {
+                unit.health = 0;
+            }
+        }
+        self.apply_hits(&displaced.collision_hits);
+    }
+
+    /// Where `target` ends up when displaced by `attacker`.
+    /// Stops at the first blocked step; bumping into a unit, the map edge or a
+    /// wall of high terrain hurts, stepping into the void is lethal.
+    fn resolve_displacement(&self, board: Board, attacker: &Unit, target: &Unit, hits: &[Hit], kind: Displacement) -> DisplacementResult {
+        let (health, shield) = after_hits(target, hits);
+        let from = target.get_pos();
+        let mut result = DisplacementResult {
+            unit: target.id,
+            kind,
+            from,
+            to: from,
+            swapped_with: None,
+            collision_hits: Vec::new(),
+            fell: false,
+        };
+
+        let (steps, dir) = match kind {
+            Displacement::Swap => {
+                result.to = attacker.get_pos();
+                result.swapped_with = Some(attacker.id);
+                return result;
+            }
+            Displacement::Push(n) => (n, direction_into(attacker.get_pos(), from)),
+            Displacement::Pull(n) => (n, direction_into(attacker.get_pos(), from).map(|d| d.invert())),
+        };
+        let Some(dir) = dir else { return result };
+
+        let grid = self.grid();
+        let mut pos = from;
+        for _ in 0..steps {
+            let next = pos.offset(dir.dir_vec());
+
+            if next == attacker.get_pos() {
+                break;  // pulled all the way in
+            }
+            // Off the map or a cliff too high to be knocked up counts as a wall
+            let wall = grid.get_height(next)
+                .zip(grid.get_height(pos))
+                .is_none_or(|(to, from)| to > from.saturating_add(MAX_CLIMB));
+            if wall {
+                result.collision_hits.push(collision_hit(target.id, health, shield));
+                break;
+            }
+            if let Some(blocker) = board.occupant(next) {
+                result.collision_hits.push(collision_hit(target.id, health, shield));
+                let (health, shield) = after_hits(blocker, hits);
+                result.collision_hits.push(collision_hit(blocker.id, health, shield));
+                break;
+            }
+
+            pos = next;
+            if grid.get_terrain_type(pos) == Some(&TerrainType::Void) {
+                result.fell = true;
+                break;
+            }
+        }
+
+        result.to = pos;
+        result
+    }
+
+    /// Copies of the units `displaced` moves, standing where it leaves them
+    fn displaced_units(&self, displaced: &DisplacementResult) -> Vec<Unit> {
+        let mut moved = Vec::new();
+        if let Some(mut unit) = self.get_unit(displaced.unit).cloned() {
+            unit.change_pos(displaced.to);
+            if displaced.fell {
+                unit.health = 0;
+            }
+            moved.push(unit);
+        }
+        if let Some(mut other) = displaced.swapped_with.and_then(|id| self.get_unit(id)).cloned() {
+            other.change_pos(displaced.from);
+            moved.push(other);
+        }
+        moved
+    }
+
+    pub(super) fn board(&self) -> Board<'_> {
+        Board::new(self, &[])
+    }
+
+    /// Whether a unit caught in the area (not the picked target) is affected
+    fn splash_affects(&self, attacker: &Unit, attack: &AttackDefinition, victim: &Unit) -> bool {
+        victim.id != attacker.id
+            && self.target_filter_allows(attack.target, attacker.owner, victim.owner)
+            && self.can_damage(attacker.owner, victim.owner)
+    }
+
+    /// Which side of `target` an attack from `from` lands on
+    pub fn attack_side(&self, from: Position, target: &Unit) -> Side {
+        let Some(&toward) = line_steps(target.get_pos(), from).first() else { return Side::Front };
+
+        match target.facing.turns_to(toward) {
+            0 | 1 => Side::Front,
+            2 => Side::Flank,
+            _ => Side::Rear,
+        }
+    }
+
+    fn compute_hit(&self, attack: &AttackDefinition, from: Position, victim: &Unit, rolls: &mut Rolls) -> Hit {
+        let definition = self.registry().get(victim.class);
+        let armor = definition.map_or(Armor::default(), |d| d.armor);
+        let evasion = definition.map_or(0.0, |d| d.evasion);
+
+        let hit_chance = (attack.hit_chance - evasion).clamp(0.0, 1.0);
+        let roll = AttackRoll::roll(rolls, hit_chance, attack.crit_chance.clamp(0.0, 1.0));
+
+        let multiplier = match roll.outcome {
+            HitOutcome::Miss => 0.0,
+            HitOutcome::Hit => 1.0,
+            HitOutcome::Crit => attack.crit_multiplier,
+        } * self.attack_side(from, victim).multiplier();
+
+        let factor = armor.damage_factor(&attack.damage_type, attack.armor_penetration);
+        let damage = (attack.base_damage as f32 * multiplier * factor).round() as u32;
+        let effects = if roll.outcome == HitOutcome::Miss { Vec::new() } else { attack.effects.to_vec() };
+
+        Hit::new(victim.id, roll, damage, victim.health, victim.shield, effects)
+    }
+}
+
+fn collision_hit(unit: UnitId, health: u32, shield: u32) -> Hit {
+    Hit::new(unit, AttackRoll::certain(), COLLISION_DAMAGE, health, shield, Vec::new())
+}
+
+/// Health and shield `unit` is left with once `hits` land
+fn after_hits(unit: &Unit, hits: &[Hit]) -> (u32, u32) {
+    hits.iter()
+        .find(|hit| hit.unit == unit.id)
+        .map_or((unit.health, unit.shield), |hit| (hit.health_after, unit.shield - hit.absorbed))
+}
+
+/// Direction of the last step on the straight line from `from` into `to`
+fn direction_into(from: Position, to: Position) -> Option<Direction> {
+    line_steps(from, to).last().copied()
+}
+
+/// Tiles covered by an attack from `from`, facing `facing`, on `target`, target first
+pub(super) fn aoe_positions(from: Position, facing: Direction, target: Position, aoe: AoePattern) -> Vec<Position> {
+    let reach = from.distance(target);
+    // Tiles within `radius` of the target, other than the two ends of the attack
+    let around = |radius: i16| {
+        let area: HexSet = shapes::disk(radius).collect();
+        area.placed_at(target).difference(&HexSet::from_iter([target, from]))
+    };
+
+    let mut positions = vec![target];
+
+    match aoe {
+        AoePattern::Single => {}
+        AoePattern::Sides => {
+            // A sweep across the attacker's facing, through the target
+            positions.extend([facing.rotated(2), facing.rotated(-2)].map(|d| target.offset(d.dir_vec())));
+        }
+        AoePattern::Radius(radius) => {
+            positions.extend(&around(radius as i16));
+        }
+        AoePattern::Cone(length) => {
+            // Tiles "behind" the target as seen from the attacker
+            positions.extend(around(length as i16)
+                .iter()
+                .filter(|&p| from.distance(p) == reach + target.distance(p)));
+        }
+        AoePattern::Line(length) => {
+            // Keep walking the same steps that led from the attacker to the target
+            let steps = line_steps(from, target);
+            let mut pos = target;
+            for step in steps.iter().cycle().take(length as usize) {
+                pos = pos.offset(step.dir_vec());
+                positions.push(pos);
+            }
+        }
+    }
+
+    positions
+}
+
+
+#[cfg(test)]
+mod tests {
+    use smallvec::smallvec;
+
+    use super::*;
+    use crate::core::combat::AttackRange;
+    use crate::core::game::state::ProposedAction;
+    use crate::core::game::testing::{self, unit_class};
+    use crate::core::player::PlayerId;
+    use crate::core::unit::{UnitClassId, UnitDefinition};
+
+    fn bomber_game() -> Game {
+        bomber_game_with(1.0, 0.0)
+    }
+
+    fn bomber_game_with(hit_chance: f32, crit_chance: f32) -> Game {
+        game_with_attack(AttackDefinition {
+            base_damage: 8,
+            range: AttackRange { inner_radius: 1, outer_radius: 2 },
+            aoe: AoePattern::Radius(1),
+            hit_chance,
+            crit_chance,
+            crit_multiplier: 2.0,
+            effects: smallvec![Effect::Stun { turns: 1 }],
+            ..testing::attack()
+        })
+    }
+
+    /// Single target, 2 damage after armor, moves the target
+    fn shover_game(displacement: Displacement) -> Game {
+        game_with_attack(AttackDefinition {
+            base_damage: 4,
+            range: AttackRange { inner_radius: 1, outer_radius: 3 },
+            displacement: Some(displacement),
+            ..testing::attack()
+        })
+    }
+
+    /// Player two's units face player one's, who attack from the left
+    fn face_attackers(game: &mut Game) {
+        let defenders: Vec<UnitId> = game.units_iter()
+            .filter(|u| u.owner == PlayerId::new(2))
+            .map(|u| u.id)
+            .collect();
+        for id in defenders {
+            game.get_unit_mut(id).unwrap().facing = Direction::Left;
+        }
+    }
+
+    /// Bombers halve all damage they take
+    fn game_with_attack(attack: AttackDefinition) -> Game {
+        testing::game()
+            .class(UnitDefinition {
+                armor: Armor::uniform(0.5),
+                attacks: vec![attack],
+                base_speed: 5,
+                ..unit_class("Bomber")
+            })
+            .build()
+    }
+
+    #[test]
+    fn test_forecast_splash() {
+        let mut game = bomber_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(2, 2), p1);  // attacker
+        game.spawn_unit(class, Position::new(4, 2), p2);  // target
+        game.spawn_unit(class, Position::new(5, 2), p2);  // splashed enemy
+        game.spawn_unit(class, Position::new(3, 2), p1);  // ally next to target, no friendly fire
+        face_attackers(&mut game);
+
+        let forecast = game.forecast(UnitId(0), AttackId::new(0), Position::new(4, 2)).unwrap();
+        let units: Vec<UnitId> = forecast.hits.iter().map(|h| h.unit).collect();
+        assert_eq!(units, vec![UnitId(1), UnitId(2)]);
+        assert_eq!(forecast.hits[0].expected_damage, 4.0);
+        assert_eq!(forecast.hits[0].kill_chance, 0.0);
+        assert_eq!(forecast.hits[0].effects, vec![Effect::Stun { turns: 1 }]);
+
+        // Nothing changed
+        assert_eq!(game.get_unit(UnitId(1)).unwrap().health, 10);
+    }
+
+    #[test]
+    fn test_forecast_matches_attack() {
+        let mut game = bomber_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(2, 2), p1);
+        game.spawn_unit(class, Position::new(3, 2), p2);
+        game.get_unit_mut(UnitId(1)).unwrap().health = 3;
+        game.commit_turn();
+
+        let forecast = game.forecast(UnitId(0), AttackId::new(0), Position::new(3, 2)).unwrap();
+        assert_eq!(forecast.hits[0].kill_chance, 1.0);
+
+        game.handle_action(p1, ProposedAction::Attack { target: UnitId(1), attack: AttackId::new(0) }).unwrap();
+        assert!(game.get_unit(UnitId(1)).is_none());
+    }
+
+    #[test]
+    fn test_aoe_shapes() {
+        let from = Position::new(2, 2);
+        let target = Position::new(3, 2);
+
+        let sides = aoe_positions(from, Direction::Right, target, AoePattern::Sides);
+        assert_eq!(sides.len(), 3);
+        assert!(sides.contains(&Position::new(3, 1)));
+        assert!(sides.contains(&Position::new(2, 3)));
+
+        // Facing away, the sweep falls on the other side of the target
+        let sides = aoe_positions(from, Direction::UpRight, target, AoePattern::Sides);
+        assert!(sides.contains(&Position::new(4, 1)) && sides.contains(&Position::new(2, 2)));
+
+        let line = aoe_positions(from, Direction::Right, target, AoePattern::Line(2));
+        assert_eq!(line, vec![target, Position::new(4, 2), Position::new(5, 2)]);
+
+        let cone = aoe_positions(from, Direction::Right, target, AoePattern::Cone(1));
+        assert_eq!(cone.len(), 4);  // target and the three tiles behind it
+        assert!(!cone.contains(&from));
+    }
+
+    #[test]
+    fn test_forecast_with_chances() {
+        let mut game = bomber_game_with(0.5, 0.5);
+        let class = UnitClassId::new(0);
+        game.spawn_unit(class, Position::new(2, 2), PlayerId::new(1));
+        game.spawn_unit(class, Position::new(3, 2), PlayerId::new(2));
+        face_attackers(&mut game);
+        game.get_unit_mut(UnitId(1)).unwrap().health = 5;
+
+        // Hit for 4 at 25%, crit for 8 (lethal) at 25%
+        let forecast = game.forecast(UnitId(0), AttackId::new(0), Position::new(3, 2)).unwrap();
+        assert_eq!(forecast.hits[0].expected_damage, 3.0);
+        assert_eq!(forecast.hits[0].kill_chance, 0.25);
+    }
+
+    #[test]
+    fn test_seeded_rolls_are_recorded_and_reproducible() {
+        let play = |seed: u64| {
+            let mut game = bomber_game_with(0.5, 0.5);
+            game.set_seed(seed);
+            let class = UnitClassId::new(0);
+            game.spawn_unit(class, Position::new(2, 2), PlayerId::new(1));
+            game.spawn_unit(class, Position::new(3, 2), PlayerId::new(2));
+            face_attackers(&mut game);
+            game.commit_turn();
+
+            game.handle_action(PlayerId::new(1), ProposedAction::Attack { target: UnitId(1), attack: AttackId::new(0) }).unwrap();
+            match game.current_changes().last() {
+                Some(ResolvedChange::Attack { hits, .. }) => hits[0].clone(),
+                _ => panic!("attack not logged"),
+            }
+        };
+
+        let hit = play(7);
+        assert_eq!(hit, play(7));
+        assert_eq!(hit.roll.outcome == HitOutcome::Miss, hit.roll.hit_roll >= 0.5);
+    }
+
+    #[test]
+    fn test_push_into_unit_collides() {
+        let mut game = shover_game(Displacement::Push(2));
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(1, 2), p1);
+        game.spawn_unit(class, Position::new(2, 2), p2);  // pushed one step
+        game.spawn_unit(class, Position::new(4, 2), p2);  // then slammed into
+        face_attackers(&mut game);
+        game.commit_turn();
+
+        let forecast = game.forecast(UnitId(0), AttackId::new(0), Position::new(2, 2)).unwrap();
+        assert_eq!(forecast.hits[0].expected_damage, 2.0 + COLLISION_DAMAGE as f32);
+        assert_eq!(forecast.hits[1].unit, UnitId(2));
+        assert_eq!(forecast.hits[1].expected_damage, COLLISION_DAMAGE as f32);
+
+        game.handle_action(p1, ProposedAction::Attack { target: UnitId(1), attack: AttackId::new(0) }).unwrap();
+
+        let pushed = game.get_unit(UnitId(1)).unwrap();
+        assert_eq!(pushed.get_pos(), Position::new(3, 2));
+        assert_eq!(pushed.health, 10 - 2 - COLLISION_DAMAGE);
+        assert_eq!(game.get_unit(UnitId(2)).unwrap().health, 10 - COLLISION_DAMAGE);
+        assert_eq!(game.grid().get_occupancy(Position::new(3, 2)), Some(UnitId(1)));
+        assert_eq!(game.grid().get_occupancy(Position::new(2, 2)), None);
+        assert!(game.current_changes().iter().any(|c| matches!(c, ResolvedChange::Displaced { .. })));
+        assert!(!game.current_changes().iter().any(|c| matches!(c, ResolvedChange::Move { .. })));
+    }
+
+    #[test]
+    fn test_splash_then_push_into_splashed() {
+        let mut game = game_with_attack(AttackDefinition {
+            base_damage: 8,
+            aoe: AoePattern::Radius(1),
+            displacement: Some(Displacement::Push(1)),
+            ..testing::attack()
+        });
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(2, 2), p1);
+        game.spawn_unit(class, Position::new(3, 2), p2);  // pushed
+        game.spawn_unit(class, Position::new(4, 2), p2);  // splashed, then slammed into
+        face_attackers(&mut game);
+        game.commit_turn();
+
+        game.handle_action(p1, ProposedAction::Attack { target: UnitId(1), attack: AttackId::new(0) }).unwrap();
+
+        // Both collisions land on top of the splash
+        assert_eq!(game.get_unit(UnitId(1)).unwrap().health, 10 - 4 - COLLISION_DAMAGE);
+        assert_eq!(game.get_unit(UnitId(2)).unwrap().health, 10 - 4 - COLLISION_DAMAGE);
+    }
+
+    #[test]
+    fn test_push_at_max_height() {
+        let mut game = shover_game(Displacement::Push(1));
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+        for x in 2..5 {
+            game.grid_mut().set_height(Position::new(x, 2), u8::MAX).unwrap();
+        }
+
+        game.spawn_unit(class, Position::new(2, 2), p1);
+        game.spawn_unit(class, Position::new(3, 2), p2);
+        face_attackers(&mut game);
+        game.commit_turn();
+
+        game.handle_action(p1, ProposedAction::Attack { target: UnitId(1), attack: AttackId::new(0) }).unwrap();
+        let pushed = game.get_unit(UnitId(1)).unwrap();
+        assert_eq!(pushed.get_pos(), Position::new(4, 2));
+        assert_eq!(pushed.health, 8);
+    }
+
+    #[test]
+    fn test_push_into_void_is_lethal() {
+        let mut game = shover_game(Displacement::Push(3));
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+        game.grid_mut().set_terrain(Position::new(4, 2), TerrainType::Void).unwrap();
+
+        game.spawn_unit(class, Position::new(2, 2), p1);
+        game.spawn_unit(class, Position::new(3, 2), p2);
+        game.commit_turn();
+
+        let forecast = game.forecast(UnitId(0), AttackId::new(0), Position::new(3, 2)).unwrap();
+        assert_eq!(forecast.hits[0].kill_chance, 1.0);
+
+        game.handle_action(p1, ProposedAction::Attack { target: UnitId(1), attack: AttackId::new(0) }).unwrap();
+        assert!(game.get_unit(UnitId(1)).is_none());
+        assert_eq!(game.grid().get_occupancy(Position::new(4, 2)), None);
+    }
+
+    #[test]
+    fn test_pull_stops_next_to_attacker() {
+        let mut game = shover_game(Displacement::Pull(5));
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(1, 2), p1);
+        game.spawn_unit(class, Position::new(4, 2), p2);
+        face_attackers(&mut game);
+        game.commit_turn();
+
+        game.handle_action(p1, ProposedAction::Attack { target: UnitId(1), attack: AttackId::new(0) }).unwrap();
+
+        let pulled = game.get_unit(UnitId(1)).unwrap();
+        assert_eq!(pulled.get_pos(), Position::new(2, 2));
+        assert_eq!(pulled.health, 8);  // no collision
+    }
+
+    #[test]
+    fn test_swap_trades_places() {
+        let mut game = shover_game(Displacement::Swap);
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(1, 2), p1);
+        game.spawn_unit(class, Position::new(3, 2), p2);
+        game.commit_turn();
+
+        game.handle_action(p1, ProposedAction::Attack { target: UnitId(1), attack: AttackId::new(0) }).unwrap();
+
+        assert_eq!(game.get_unit(UnitId(0)).unwrap().get_pos(), Position::new(3, 2));
+        assert_eq!(game.get_unit(UnitId(1)).unwrap().get_pos(), Position::new(1, 2));
+        assert_eq!(game.grid().get_occupancy(Position::new(3, 2)), Some(UnitId(0)));
+        assert_eq!(game.grid().get_occupancy(Position::new(1, 2)), Some(UnitId(1)));
+    }
+
+    #[test]
+    fn test_flank_and_rear_bonus() {
+        let mut game = bomber_game();
+        let class = UnitClassId::new(0);
+        game.spawn_unit(class, Position::new(2, 2), PlayerId::new(1));
+        game.spawn_unit(class, Position::new(3, 2), PlayerId::new(2));
+        let from = Position::new(2, 2);
+
+        // 8 damage, halved by armor, then 1x / 1.25x / 1.5x
+        for (facing, side, damage) in [
+            (Direction::Left, Side::Front, 4.0),
+            (Direction::UpLeft, Side::Front, 4.0),
+            (Direction::UpRight, Side::Flank, 5.0),
+            (Direction::Right, Side::Rear, 6.0),
+        ] {
+            game.get_unit_mut(UnitId(1)).unwrap().facing = facing;
+            assert_eq!(game.attack_side(from, game.get_unit(UnitId(1)).unwrap()), side);
+
+            let forecast = game.forecast(UnitId(0), AttackId::new(0), Position::new(3, 2)).unwrap();
+            assert_eq!(forecast.hits[0].expected_damage, damage);
+        }
+    }
+}
@@ -1,13 +1,18 @@
 #![allow(dead_code, warnings)]
 
-use std::collections::HashMap;
-use crate::core::unit::{Unit, UnitId};
+use std::collections::{HashMap, HashSet};
+use crate::core::unit::{ClassRegistry, Unit, UnitId};
 use crate::core::grid::Grid;
 use crate::core::turn::UnitQueue;
 use crate::core::geom::{Direction, Path, Position, position};
-use crate::core::player::{PlayerId, Player};
-use crate::core::combat::AttackId;
+use crate::core::player::{PlayerId, Player, PlayerStatus};
+use crate::core::combat::{AbilityId, AbilityTarget, ActionKey, AttackId, CooldownTick, ReactionTrigger, TargetFilter};
 use crate::core::unit::UnitClassId;
+use crate::core::rng::Rng;
+use super::victory::{Annihilation, GameOutcome, VictoryCondition};
+use super::ability::AbilityResolution;
+use super::healing::{Heal, ShieldChange};
+use super::resolution::{DisplacementResult, Hit};
 
 
 /// Game is divided into rounds and turns.
@@ -18,8 +23,10 @@ use crate::core::unit::UnitClassId;
 /// the new queue is constructed and the round proceeds as usual.
 pub struct Game {
     players: HashMap<PlayerId, Player>,
+    registry: ClassRegistry,
     
     units: HashMap<UnitId, Unit>,
+    next_id: UnitId,  // never reused, dead units keep theirs for rollback
     grid: Grid,
     queue: UnitQueue,
 
@@ -29,8 +36,32 @@ pub struct Game {
     curr_turn: Turn,
     history: Vec<Turn>,  // turn history
     snapshots: Vec<RoundSnapshot>,  // round snapshots
+
+    spawn_budget: u32,  // units each player may spawn per round
+    spawns_this_round: HashMap<PlayerId, u32>,
+
+    death_hooks: Vec<DeathHook>,
+
+    victory_conditions: Vec<Box<dyn VictoryCondition>>,
+    outcome: Option<GameOutcome>,  // set once the match is over
+
+    friendly_fire: bool,  // whether attacks and AoE can hurt allies
+    shared_vision: bool,  // whether allies see what each other's units see
+
+    rng: Rng,  // every random roll of the match comes from here
+
+    reacted: HashSet<UnitId>,  // units that used their reaction this round
+
+    movement_spent: u32,    // by the unit whose turn it is
+    stopped_in_zone: bool,  // it entered an enemy zone of control, no moving on
 }
 
+/// Called with the game and the unit that just died, after it was removed
+pub type DeathHook = Box<dyn FnMut(&mut Game, &Unit)>;
+
+/// Units each player may spawn per round unless configured otherwise
+pub const DEFAULT_SPAWN_BUDGET: u32 = 3;
+
 struct Turn {
     turn_number: u32,
     phase: RoundPhase,  // spawn phase, unit phase
@@ -38,27 +69,43 @@ struct Turn {
 }
 
 impl Game {
-    pub fn new(players: HashMap<PlayerId, Player>, grid: Grid) -> Self {
+    pub fn new(players: HashMap<PlayerId, Player>, grid: Grid, registry: ClassRegistry) -> Self {
         Game {
             players,
+            registry,
             units: HashMap::new(),
+            next_id: UnitId(0),
             grid,
-            queue: UnitQueue::new(),
+            queue: UnitQueue::new(&[]),
             round_number: 1,
             turn_number: 1,
-            curr_turn: Turn::new(1, ),
+            curr_turn: Turn::new(1, RoundPhase::SpawnPhase),
             history: Vec::new(),
             snapshots: Vec::new(),
+            spawn_budget: DEFAULT_SPAWN_BUDGET,
+            spawns_this_round: HashMap::new(),
+            death_hooks: Vec::new(),
+            victory_conditions: vec![Box::new(Annihilation)],
+            outcome: None,
+            friendly_fire: false,
+            shared_vision: true,
+            rng: Rng::new(0),
+
+            reacted: HashSet::new(),
+
+            movement_spent: 0,
+            stopped_in_zone: false,
         }
     }
 
-    fn next_unit_id(&self) -> Option<UnitId> {
-        self.units
-            .keys()
-            .copied()
-            .max()
-            .map(|max_id| max_id.next())
-            .unwrap_or(Some(UnitId(0)))
+    pub fn set_spawn_budget(&mut self, budget: u32) {
+        self.spawn_budget = budget;
+    }
+
+    /// How many more units `player` may spawn this round
+    pub fn remaining_spawns(&self, player: PlayerId) -> u32 {
+        let used = self.spawns_this_round.get(&player).copied().unwrap_or(0);
+        self.spawn_budget.saturating_sub(used)
     }
 
     fn apply_resolution(&mut self, change: ResolvedChange) {
@@ -66,10 +113,58 @@ impl Game {
             ResolvedChange::Move { unit_id, path } => {
                 self.move_unit(unit_id, path);
             },
+            ResolvedChange::Turn { unit_id, to, .. } => {
+                self.turn_unit(unit_id, to);
+            }
             ResolvedChange::Spawn { unit, owner, position } => {
                 self.spawn_unit(unit, position, owner);
             }
-            _ => unimplemented!()
+            ResolvedChange::Purchase { player, cost, .. } => {
+                if let Some(p) = self.players.get_mut(&player) { p.spend(cost); }
+            }
+            ResolvedChange::Income { player, amount } => {
+                if let Some(p) = self.players.get_mut(&player) { p.earn(amount); }
+            }
+            ResolvedChange::Attack { attacker, attack, hits, .. } => {
+                self.record_usage(attacker, ActionKey::Attack(attack));
+                self.apply_hits(&hits);
+            }
+            ResolvedChange::Ability { resolution } => {
+                self.apply_ability(&resolution);
+            }
+            ResolvedChange::Heal { heal, .. } | ResolvedChange::Regeneration { heal } => {
+                self.apply_heal(&heal);
+            }
+            ResolvedChange::Shield { change, .. } => {
+                self.apply_shield(&change);
+            }
+            ResolvedChange::Reaction { unit, attack, hits, .. } => {
+                self.record_usage(unit, ActionKey::Attack(attack));
+                self.apply_hits(&hits);
+            }
+            ResolvedChange::Displaced { displacement } => {
+                self.apply_displacement(&displacement);
+            }
+            ResolvedChange::Death { unit } => {
+                self.remove_unit(unit.id);
+            }
+            ResolvedChange::Eliminated { player } => {
+                if let Some(p) = self.players.get_mut(&player) { p.set_status(PlayerStatus::Eliminated); }
+            }
+            ResolvedChange::Surrender { player } => {
+                if let Some(p) = self.players.get_mut(&player) { p.set_status(PlayerStatus::Surrendered); }
+            }
+            ResolvedChange::Score { player, points } => {
+                if let Some(p) = self.players.get_mut(&player) { p.add_score(points); }
+            }
+            ResolvedChange::GameOver { outcome } => {
+                self.curr_turn.phase = RoundPhase::GameOver;
+                self.outcome = Some(outcome);
+            }
+            ResolvedChange::EndTurn => {
+                // Only marks where the player stopped, what the next turn
+                // starts with (regeneration, expiring shields) is its own change
+            }
         }
     }
 
@@ -77,24 +172,147 @@ impl Game {
         self.units.values()
     }
 
+    /// Validate a client's proposed action and, if legal, resolve it
+    pub fn handle_action(&mut self, player: PlayerId, action: ProposedAction) -> Result<(), GameError> {
+        if self.outcome.is_some() {
+            return Err(GameError::GameOver);
+        }
+        if !self.players.contains_key(&player) {
+            return Err(GameError::UnknownPlayer);
+        }
+        if !self.is_active(player) {
+            return Err(GameError::PlayerEliminated);
+        }
+
+        let result = match action {
+            ProposedAction::Spawn { unit, position } => {
+                self.validate_spawn(player, unit, position)?;
+
+                let cost = self.registry.get(unit).map_or(0, |d| d.cost);
+                self.players.get_mut(&player).unwrap().spend(cost);
+                self.curr_turn.log_change(ResolvedChange::Purchase { player, unit, cost });
+
+                self.spawn_unit(unit, position, player);
+                *self.spawns_this_round.entry(player).or_insert(0) += 1;
+                Ok(())
+            }
+            ProposedAction::Attack { target, attack } => {
+                self.validate_attack(player, target, attack)?;
+
+                let attacker = self.current_unit().ok_or(GameError::WrongPhase)?;
+                let target_pos = self.units[&target].get_pos();
+                self.perform_attack(attacker, attack, target_pos)
+            }
+            ProposedAction::Move { path } => {
+                self.validate_move(player, &path)?;
+
+                let mover = self.current_unit().ok_or(GameError::WrongPhase)?;
+                self.perform_move(mover, path);
+                Ok(())
+            }
+            ProposedAction::Turn { facing } => {
+                let unit = self.current_unit().ok_or(GameError::WrongPhase)?;
+                if self.get_unit(unit).ok_or(GameError::InvalidUnit)?.owner != player {
+                    return Err(GameError::NotYourTurn);
+                }
+
+                self.turn_unit(unit, facing);
+                Ok(())
+            }
+            ProposedAction::Ability { ability, target } => {
+                self.validate_ability(player, ability, target)?;
+
+                let caster = self.current_unit().ok_or(GameError::WrongPhase)?;
+                self.perform_ability(caster, ability, target)
+            }
+            ProposedAction::Surrender => {
+                self.surrender(player);
+                Ok(())
+            }
+            ProposedAction::EndTurn => {
+                // The spawn phase is shared by every player, the server ends it.
+                // Anyone may end the turn of a unit that was killed from outside
+                let unit = self.current_unit().ok_or(GameError::WrongPhase)?;
+                if self.get_unit(unit).is_some_and(|u| u.owner != player) {
+                    return Err(GameError::NotYourTurn);
+                }
+
+                self.curr_turn.log_change(ResolvedChange::EndTurn);
+                self.commit_turn();
+                Ok(())
+            }
+        };
+
+        // The acting unit may have died to a reaction, nobody is left to end its turn
+        if self.current_unit().is_some_and(|unit| !self.units.contains_key(&unit)) {
+            self.commit_turn();
+        }
+        result
+    }
+
+    pub fn validate_spawn(&self, player: PlayerId, unit_class: UnitClassId, pos: Position) -> Result<(), GameError> {
+        if !matches!(self.curr_turn.phase, RoundPhase::SpawnPhase) {
+            return Err(GameError::WrongPhase);
+        }
+        if !self.players.contains_key(&player) {
+            return Err(GameError::UnknownPlayer);
+        }
+        if !self.is_active(player) {
+            return Err(GameError::PlayerEliminated);
+        }
+        let Some(definition) = self.registry.get(unit_class) else {
+            return Err(GameError::UnknownUnitClass);
+        };
+        if !self.players[&player].can_afford(definition.cost) {
+            return Err(GameError::NotEnoughResources);
+        }
+        if self.remaining_spawns(player) == 0 {
+            return Err(GameError::SpawnBudgetExceeded);
+        }
+        if !self.grid.is_spawn_tile(player, pos)
+            || !self.grid.get_terrain_type(pos).is_some_and(|t| t.is_walkable())
+        {
+            return Err(GameError::InvalidPosition);
+        }
+        if self.grid.get_occupancy(pos).is_some() {
+            return Err(GameError::TileOccupied);
+        }
+
+        Ok(())
+    }
+
     pub fn spawn_unit(&mut self, unit_class: UnitClassId, pos: Position, owner: PlayerId) {
-        let new_unit_id: UnitId = self.next_unit_id().unwrap();
+        let new_unit_id = self.next_id;
+        self.next_id = new_unit_id.next().expect("unit ids exhausted");
         
-        let new_unit = Unit::new(
+        let mut new_unit = Unit::new(
             unit_class,
             owner,
             pos,
             new_unit_id);
+
+        if let Some(definition) = self.registry.get(unit_class) {
+            new_unit.health = definition.base_health.max(0) as u32;
+        }
         
         self.units.insert(new_unit_id, new_unit);
-        self.grid.set_occupancy(pos, Some(new_unit_id));
+        self.grid.set_occupancy(pos, Some(new_unit_id)).expect("spawn position was validated");
+
+        self.curr_turn.log_change(ResolvedChange::Spawn {
+            unit: unit_class,
+            owner,
+            position: pos,
+        });
     }
 
     pub fn move_unit(&mut self, unit_id: UnitId, path: Path) {
         let unit = self.units.get_mut(&unit_id).expect("Invalid unit_id");
 
         unit.change_pos(path.end());  // Update unit
-        self.grid.move_occupancy(path.start(), path.end());  // Update grid
+        if let Some(step) = path.last_step() {
+            unit.facing = step;
+        }
+        self.grid.move_occupancy(path.start(), path.end()).expect("path was validated");  // Update grid
 
         // Log the change in the current turn
         self.curr_turn.log_change(ResolvedChange::Move {
@@ -103,10 +321,75 @@ impl Game {
         });
     }
 
+    pub fn turn_unit(&mut self, unit_id: UnitId, facing: Direction) {
+        let unit = self.units.get_mut(&unit_id).expect("Invalid unit_id");
+        let from = std::mem::replace(&mut unit.facing, facing);
+
+        self.curr_turn.log_change(ResolvedChange::Turn {
+            unit_id,
+            from,
+            to: facing,
+        });
+    }
+
+    pub fn add_death_hook(&mut self, hook: DeathHook) {
+        self.death_hooks.push(hook);
+    }
+
+    /// Remove a unit from the game, grid and queue without logging anything
+    fn remove_unit(&mut self, unit_id: UnitId) -> Option<Unit> {
+        let unit = self.units.remove(&unit_id)?;
+
+        if self.grid.get_occupancy(unit.get_pos()) == Some(unit_id) {
+            self.grid.set_occupancy(unit.get_pos(), None).expect("occupied tiles are in bounds");
+        }
+        self.queue.remove_unit(unit_id);
+
+        Some(unit)
+    }
+
+    /// Remove a unit everywhere, log its full state so clients can roll back,
+    /// then run the on-death hooks
+    pub fn kill_unit(&mut self, unit_id: UnitId) {
+        let Some(unit) = self.remove_unit(unit_id) else { return };
+
+        self.curr_turn.log_change(ResolvedChange::Death { unit: unit.clone() });
+
+        // Hooks get `&mut Game`, so they are taken out while running
+        let mut hooks = std::mem::take(&mut self.death_hooks);
+        for hook in hooks.iter_mut() {
+            hook(self, &unit);
+        }
+        hooks.append(&mut self.death_hooks);  // keep hooks added by hooks
+        self.death_hooks = hooks;
+    }
+
+    /// Kill every unit at 0 health, in id order, until none are left
+    /// (hooks may bring other units down). The match can end right there.
+    pub fn remove_dead_units(&mut self) {
+        loop {
+            let mut dead: Vec<UnitId> = self.units
+                .values()
+                .filter(|u| u.health == 0)
+                .map(|u| u.id)
+                .collect();
+            if dead.is_empty() {
+                break;
+            }
+
+            dead.sort();
+            for unit_id in dead {
+                self.kill_unit(unit_id);
+            }
+        }
+
+        self.check_victory(false);
+    }
+
     fn update_turn(&mut self, next_unit: UnitId) {
         let old_turn = std::mem::replace(
                 &mut self.curr_turn,
-                Turn::new(self.turn_number + 1, next_unit)
+                Turn::new(self.turn_number + 1, RoundPhase::UnitTurn { unit: next_unit })
             );
             self.history.push(old_turn);
 
@@ -114,6 +397,11 @@ impl Game {
     }
 
     pub fn commit_turn(&mut self) {
+        // The match is over, nothing advances anymore
+        if self.outcome.is_some() {
+            return;
+        }
+
         // Move current turn into history
         let old_turn = std::mem::replace(
             &mut self.curr_turn,
@@ -128,7 +416,9 @@ impl Game {
         self.history.push(old_turn);
         self.turn_number += 1;
 
-        match &self.history.last().unwrap().phase {
+        let mut round_ended = false;
+
+        match finished_phase {
             RoundPhase::SpawnPhase => {
                 // Spawn phase just ended
 
@@ -136,41 +426,323 @@ impl Game {
                 self.queue.reset_from_game(&self.units);
 
                 // First unit of round
-                let first_unit = self.queue
-                    .next_unit()
-                    .expect("Queue must contain at least one unit");
-
-                self.curr_turn.phase = RoundPhase::UnitTurn { unit: first_unit };
+                match self.queue.next_alive(&self.units) {
+                    Some(first_unit) => {
+                        self.begin_unit_turn(first_unit);
+                    }
+                    None => {
+                        // Nobody on the board, skip straight to the next round
+                        self.end_round();
+                        round_ended = true;
+                    }
+                }
             }
 
             RoundPhase::UnitTurn { .. } => {
                 // Normal unit turn ended
 
-                match self.queue.next_unit() {
+                match self.queue.next_alive(&self.units) {
                     Some(next_unit) => {
                         // Continue same round
-                        self.curr_turn.phase = RoundPhase::UnitTurn { unit: next_unit };
+                        self.begin_unit_turn(next_unit);
                     }
                     None => {
-                        // End of round
-                        self.snapshot_round();
-
-                        self.round_number += 1;
-
-                        // Start next round in spawn phase
-                        self.curr_turn.phase = RoundPhase::SpawnPhase;
+                        self.end_round();
+                        round_ended = true;
                     }
                 }
             }
+
+            RoundPhase::GameOver => unreachable!("turns are not committed once the game is over"),
+        }
+
+        self.check_victory(round_ended);
+    }
+
+    fn begin_unit_turn(&mut self, unit: UnitId) {
+        self.curr_turn.phase = RoundPhase::UnitTurn { unit };
+        self.movement_spent = 0;
+        self.stopped_in_zone = false;
+
+        if let Some(unit) = self.units.get_mut(&unit) {
+            unit.cooldowns.tick(CooldownTick::OwnTurn);
+        }
+        self.regenerate(unit);
+    }
+
+    fn end_round(&mut self) {
+        self.expire_shields();
+        self.snapshot_round();
+
+        self.round_number += 1;
+
+        // Start next round in spawn phase
+        self.curr_turn.phase = RoundPhase::SpawnPhase;
+        self.spawns_this_round.clear();
+        self.reacted.clear();
+        for unit in self.units.values_mut() {
+            unit.cooldowns.tick(CooldownTick::Round);
         }
+        self.collect_income();
+    }
+
+    pub fn add_victory_condition(&mut self, condition: Box<dyn VictoryCondition>) {
+        self.victory_conditions.push(condition);
+    }
+
+    /// Replace the default conditions (annihilation only)
+    pub fn set_victory_conditions(&mut self, conditions: Vec<Box<dyn VictoryCondition>>) {
+        self.victory_conditions = conditions;
+    }
+
+    /// Eliminate players that can no longer act, then evaluate the victory
+    /// conditions in order. The first outcome ends the match.
+    fn check_victory(&mut self, round_ended: bool) {
+        if self.outcome.is_some() {
+            return;
+        }
+
+        self.update_eliminations();
+
+        // Conditions get `&Game`, so they are taken out while running
+        let mut conditions = std::mem::take(&mut self.victory_conditions);
+        if round_ended {
+            for condition in conditions.iter_mut() {
+                condition.on_round_end(self);
+            }
+        }
+        let outcome = conditions.iter_mut().find_map(|c| c.check(self));
+        self.victory_conditions = conditions;
+
+        if let Some(outcome) = outcome {
+            self.end_game(outcome);
+        }
+    }
+
+    /// Victory is shared with the whole team, eliminated members included
+    fn end_game(&mut self, outcome: GameOutcome) {
+        let outcome = match outcome {
+            GameOutcome::Victory { winners } => {
+                let mut winners: Vec<PlayerId> = self.players
+                    .keys()
+                    .copied()
+                    .filter(|&p| winners.iter().any(|&w| self.are_allies(w, p)))
+                    .collect();
+                winners.sort();
+                GameOutcome::Victory { winners }
+            }
+            GameOutcome::Draw => GameOutcome::Draw,
+        };
+
+        self.curr_turn.phase = RoundPhase::GameOver;
+        self.curr_turn.log_change(ResolvedChange::GameOver { outcome: outcome.clone() });
+        self.outcome = Some(outcome);
+    }
+
+    /// Active players with no units left who can't afford any unit are out
+    fn update_eliminations(&mut self) {
+        let cheapest = self.registry.classes.values().map(|d| d.cost).min();
+
+        let mut eliminated: Vec<PlayerId> = self.players
+            .iter()
+            .filter(|(id, player)| {
+                player.is_active()
+                    && !self.units.values().any(|u| u.owner == **id)
+                    && !cheapest.is_some_and(|cost| player.can_afford(cost))
+            })
+            .map(|(&id, _)| id)
+            .collect();
+        eliminated.sort();
+
+        for player in eliminated {
+            self.players.get_mut(&player).unwrap().set_status(PlayerStatus::Eliminated);
+            self.curr_turn.log_change(ResolvedChange::Eliminated { player });
+        }
+    }
+
+    fn surrender(&mut self, player: PlayerId) {
+        self.players.get_mut(&player).unwrap().set_status(PlayerStatus::Surrendered);
+        self.curr_turn.log_change(ResolvedChange::Surrender { player });
+
+        let mut units: Vec<UnitId> = self.units
+            .values()
+            .filter(|u| u.owner == player)
+            .map(|u| u.id)
+            .collect();
+        units.sort();
+
+        for unit_id in units {
+            self.kill_unit(unit_id);
+        }
+
+        self.check_victory(false);
+    }
+
+    pub fn award_score(&mut self, player: PlayerId, points: u32) {
+        if let Some(p) = self.players.get_mut(&player) {
+            p.add_score(points);
+            self.curr_turn.log_change(ResolvedChange::Score { player, points });
+        }
+    }
+
+    pub fn outcome(&self) -> Option<&GameOutcome> {
+        self.outcome.as_ref()
+    }
+
+    pub fn is_active(&self, player: PlayerId) -> bool {
+        self.players.get(&player).is_some_and(|p| p.is_active())
+    }
+
+    /// Players still in the match, sorted by id
+    pub fn active_players(&self) -> Vec<PlayerId> {
+        let mut active: Vec<PlayerId> = self.players
+            .iter()
+            .filter(|(_, p)| p.is_active())
+            .map(|(&id, _)| id)
+            .collect();
+        active.sort();
+        active
+    }
+
+    /// A player is always allied with itself
+    pub fn are_allies(&self, a: PlayerId, b: PlayerId) -> bool {
+        if a == b {
+            return true;
+        }
+
+        let team = |p: PlayerId| self.players.get(&p).and_then(|player| player.team());
+        team(a).is_some() && team(a) == team(b)
+    }
+
+    pub fn are_enemies(&self, a: PlayerId, b: PlayerId) -> bool {
+        !self.are_allies(a, b)
+    }
+
+    /// Whether `viewer` gets to see what `owner`'s units see
+    pub fn shares_vision(&self, viewer: PlayerId, owner: PlayerId) -> bool {
+        viewer == owner || (self.shared_vision && self.are_allies(viewer, owner))
+    }
+
+    /// Whether `attacker` may hurt units of `target`, honoring friendly fire
+    pub fn can_damage(&self, attacker: PlayerId, target: PlayerId) -> bool {
+        self.friendly_fire || self.are_enemies(attacker, target)
+    }
+
+    /// Whether a unit of `target` is a legal pick for an attack with `filter`
+    pub fn target_filter_allows(&self, filter: TargetFilter, attacker: PlayerId, target: PlayerId) -> bool {
+        filter.allows(self.are_allies(attacker, target))
+    }
+
+    /// Seed the match RNG. Same seed and same actions, same match.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// Whether `unit` already used its reaction this round
+    pub fn has_reacted(&self, unit: UnitId) -> bool {
+        self.reacted.contains(&unit)
+    }
+
+    pub(super) fn mark_reacted(&mut self, unit: UnitId) {
+        self.reacted.insert(unit);
+    }
+
+    /// Movement points the unit whose turn it is has walked so far
+    pub fn movement_spent(&self) -> u32 {
+        self.movement_spent
+    }
+
+    /// Whether the unit whose turn it is was stopped by an enemy zone of control
+    pub fn stopped_in_zone(&self) -> bool {
+        self.stopped_in_zone
+    }
+
+    /// Charge the unit whose turn it is for one step, `stopped` if the step
+    /// entered an enemy zone of control
+    pub(super) fn spend_movement(&mut self, cost: u32, stopped: bool) {
+        self.movement_spent = self.movement_spent.saturating_add(cost);
+        self.stopped_in_zone |= stopped;
+    }
+
+    pub(super) fn rng(&self) -> &Rng {
+        &self.rng
+    }
+
+    pub(super) fn set_rng(&mut self, rng: Rng) {
+        self.rng = rng;
+    }
+
+    pub fn set_friendly_fire(&mut self, enabled: bool) {
+        self.friendly_fire = enabled;
+    }
+
+    pub fn set_shared_vision(&mut self, enabled: bool) {
+        self.shared_vision = enabled;
+    }
+
+    pub fn round_number(&self) -> u32 {
+        self.round_number
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub(super) fn grid_mut(&mut self) -> &mut Grid {
+        &mut self.grid
+    }
+
+    pub fn registry(&self) -> &ClassRegistry {
+        &self.registry
+    }
+
+    /// The unit whose turn it is, if in a unit turn
+    pub fn current_unit(&self) -> Option<UnitId> {
+        match self.curr_turn.phase {
+            RoundPhase::UnitTurn { unit } => Some(unit),
+            _ => None,
+        }
+    }
+
+    /// Pay every player their base income plus the income of tiles their units stand on
+    fn collect_income(&mut self) {
+        let mut earnings: HashMap<PlayerId, u32> = self.players
+            .iter()
+            .map(|(&id, player)| (id, player.income()))
+            .collect();
+
+        for unit in self.units.values() {
+            if let Some(total) = earnings.get_mut(&unit.owner) {
+                *total += self.grid.tile_income(unit.get_pos());
+            }
+        }
+
+        // Sorted so every peer logs the changes in the same order
+        let mut earnings: Vec<(PlayerId, u32)> = earnings.into_iter().collect();
+        earnings.sort();
+
+        for (player, amount) in earnings {
+            self.players.get_mut(&player).unwrap().earn(amount);
+            self.curr_turn.log_change(ResolvedChange::Income { player, amount });
+        }
+    }
+
+    pub fn get_player(&self, player: PlayerId) -> Option<&Player> {
+        self.players.get(&player)
+    }
+
+    pub(super) fn get_player_mut(&mut self, player: PlayerId) -> Option<&mut Player> {
+        self.players.get_mut(&player)
     }
 
     pub fn snapshot_round(&mut self) {
         let snap = RoundSnapshot {
             players: self.players.clone(),
             units: self.units.clone(),
+            next_id: self.next_id,
             grid: self.grid.clone(),
             queue: self.queue.clone(),
+            rng: self.rng.clone(),
             round_number: self.round_number,
         };
         self.snapshots.push(snap);
@@ -179,14 +751,35 @@ impl Game {
     pub fn get_unit(&self, unit_id: UnitId) -> Option<&Unit> {
         self.units.get(&unit_id)
     }
+
+    pub(super) fn get_unit_mut(&mut self, unit_id: UnitId) -> Option<&mut Unit> {
+        self.units.get_mut(&unit_id)
+    }
+
+    /// Changes resolved so far this turn, in order, as broadcast to clients
+    pub fn current_changes(&self) -> &[ResolvedChange] {
+        &self.curr_turn.changes
+    }
+
+    /// Changes of the last finished turn, which an action can end on its own
+    /// when the acting unit dies
+    pub fn previous_changes(&self) -> &[ResolvedChange] {
+        self.history.last().map_or(&[], |turn| &turn.changes)
+    }
+
+    pub(super) fn log_change(&mut self, change: ResolvedChange) {
+        self.curr_turn.log_change(change);
+    }
 }
 
 #[derive(Clone)]
 struct RoundSnapshot {
     players: HashMap<PlayerId, Player>,
     units: HashMap<UnitId, Unit>,
+    next_id: UnitId,
     grid: Grid,
     queue: UnitQueue,
+    rng: Rng,
 
     round_number: u32,
 }
@@ -200,21 +793,75 @@ pub enum ResolvedChange {
         unit_id: UnitId,
         path: Path,
     },
+    /// Turning in place, moving turns a unit on its own
+    Turn {
+        unit_id: UnitId,
+        from: Direction,
+        to: Direction,
+    },
     Attack {
         attacker: UnitId,
         target: UnitId,
         attack: AttackId,
-        // should include attack resolution
-        // and previous health to restore
+        hits: Vec<Hit>,  // each carries the previous health to restore
+    },
+    /// An attack made outside the unit's turn, inside another action's resolution
+    Reaction {
+        unit: UnitId,
+        trigger: ReactionTrigger,
+        target: UnitId,
+        attack: AttackId,
+        hits: Vec<Hit>,
+    },
+    Heal {
+        healer: UnitId,
+        heal: Heal,
+    },
+    Shield {
+        source: Option<UnitId>,  // None when the shield expires
+        change: ShieldChange,
+    },
+    /// Healing from a regeneration effect at the start of the unit's turn
+    Regeneration {
+        heal: Heal,
+    },
+    /// Forced movement (push, pull, swap), never a unit's own move
+    Displaced {
+        displacement: DisplacementResult,
     },
     Ability {
-        unit_id: UnitId,
+        resolution: AbilityResolution,
     },
     Spawn {
         unit: UnitClassId,
         owner: PlayerId,
         position: Position,
     },
+    Purchase {
+        player: PlayerId,
+        unit: UnitClassId,
+        cost: u32,
+    },
+    Income {
+        player: PlayerId,
+        amount: u32,
+    },
+    Death {
+        unit: Unit,  // state right before removal
+    },
+    Score {
+        player: PlayerId,
+        points: u32,
+    },
+    Eliminated {
+        player: PlayerId,
+    },
+    Surrender {
+        player: PlayerId,
+    },
+    GameOver {
+        outcome: GameOutcome,
+    },
     EndTurn,
 }
 
@@ -241,14 +888,18 @@ pub enum ProposedAction {
         target: UnitId,
         attack: AttackId,
     },
+    Turn {
+        facing: Direction,
+    },
     Ability {
-        //ability: AbilityId,
-        target: Option<Unit>,
+        ability: AbilityId,
+        target: AbilityTarget,
     },
     Spawn {
-        unit: UnitId,
+        unit: UnitClassId,
         position: Position,
     },
+    Surrender,
     EndTurn,
 }
 
@@ -260,10 +911,343 @@ pub enum GameError {
     OutOfRange,
     NotEnoughResources,
     IllegalAction,
+    WrongPhase,
+    UnknownPlayer,
+    UnknownUnitClass,
+    SpawnBudgetExceeded,
+    InvalidPosition,
+    TileOccupied,
+    PlayerEliminated,
+    GameOver,
+    UnknownAttack,
+    InvalidTarget,
+    NoLineOfSight,
+    UnknownAbility,
+    OnCooldown,
+    NoChargesLeft,
+    ZoneOfControl,  // path goes on after entering an enemy's zone of control
 }
 
 #[derive(Clone)]
 pub enum RoundPhase {
     SpawnPhase,                 // special first "turn"
     UnitTurn { unit: UnitId },  // normal turn
-}
\ No newline at end of file
+    GameOver,                   // terminal, the match has an outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::game::testing::{self, unit_class};
+    use crate::core::grid::TerrainType;
+    use crate::core::unit::UnitDefinition;
+
+    /// Spearmen cost 4 of each player's 10 gold, (0, 1) is void
+    fn test_game() -> Game {
+        let p1 = PlayerId::new(1);
+        let p2 = PlayerId::new(2);
+
+        let mut grid = Grid::new(8, 8);
+        grid.set_spawn_zone(p1, vec![Position::new(0, 0), Position::new(1, 0), Position::new(0, 1)]).unwrap();
+        grid.set_spawn_zone(p2, vec![Position::new(7, 7), Position::new(6, 7)]).unwrap();
+        grid.set_terrain(Position::new(0, 1), TerrainType::Void).unwrap();
+
+        testing::game().class(spearman()).grid(grid).gold(p1, 10).gold(p2, 10).build()
+    }
+
+    fn spearman() -> UnitDefinition {
+        UnitDefinition { cost: 4, ..unit_class("Spearman") }
+    }
+
+    fn spawn(class: u32, x: usize, y: usize) -> ProposedAction {
+        ProposedAction::Spawn { unit: UnitClassId::new(class), position: Position::new(x, y) }
+    }
+
+    #[test]
+    fn test_spawn_in_own_zone() {
+        let mut game = test_game();
+        let p1 = PlayerId::new(1);
+
+        assert!(game.handle_action(p1, spawn(0, 0, 0)).is_ok());
+        assert_eq!(game.units_iter().count(), 1);
+        assert_eq!(game.units_iter().next().unwrap().health, 10);
+        assert_eq!(game.remaining_spawns(p1), DEFAULT_SPAWN_BUDGET - 1);
+    }
+
+    #[test]
+    fn test_spawn_rejected() {
+        let mut game = test_game();
+        let p1 = PlayerId::new(1);
+
+        assert!(matches!(game.handle_action(p1, spawn(0, 7, 7)), Err(GameError::InvalidPosition)));
+        assert!(matches!(game.handle_action(p1, spawn(0, 0, 1)), Err(GameError::InvalidPosition)));
+        assert!(matches!(game.handle_action(p1, spawn(5, 0, 0)), Err(GameError::UnknownUnitClass)));
+        assert!(matches!(game.handle_action(PlayerId::new(9), spawn(0, 0, 0)), Err(GameError::UnknownPlayer)));
+
+        game.handle_action(p1, spawn(0, 0, 0)).unwrap();
+        assert!(matches!(game.handle_action(p1, spawn(0, 0, 0)), Err(GameError::TileOccupied)));
+    }
+
+    #[test]
+    fn test_spawn_budget() {
+        let mut game = test_game();
+        let p1 = PlayerId::new(1);
+        game.set_spawn_budget(1);
+
+        game.handle_action(p1, spawn(0, 0, 0)).unwrap();
+        assert!(matches!(game.handle_action(p1, spawn(0, 1, 0)), Err(GameError::SpawnBudgetExceeded)));
+    }
+
+    #[test]
+    fn test_spawn_costs_gold() {
+        let mut game = test_game();
+        let p1 = PlayerId::new(1);
+
+        game.handle_action(p1, spawn(0, 0, 0)).unwrap();
+        game.handle_action(p1, spawn(0, 1, 0)).unwrap();
+        assert_eq!(game.get_player(p1).unwrap().gold(), 2);
+
+        game.grid.set_spawn_zone(p1, vec![Position::new(2, 0)]).unwrap();
+        assert!(matches!(game.handle_action(p1, spawn(0, 2, 0)), Err(GameError::NotEnoughResources)));
+        assert!(game.curr_turn.changes.iter().any(|c| matches!(c, ResolvedChange::Purchase { cost: 4, .. })));
+    }
+
+    #[test]
+    fn test_income_from_controlled_tiles() {
+        let mut game = test_game();
+        let p1 = PlayerId::new(1);
+        let p2 = PlayerId::new(2);
+
+        game.players.get_mut(&p1).unwrap().set_income(2);
+        game.grid.set_tile_income(Position::new(0, 0), 3).unwrap();
+        game.handle_action(p1, spawn(0, 0, 0)).unwrap();
+
+        game.collect_income();
+        assert_eq!(game.get_player(p1).unwrap().gold(), 6 + 2 + 3);
+        assert_eq!(game.get_player(p2).unwrap().gold(), 10);
+    }
+
+    #[test]
+    fn test_end_turn() {
+        let mut game = test_game();
+        let p1 = PlayerId::new(1);
+        let p2 = PlayerId::new(2);
+        game.handle_action(p1, spawn(0, 0, 0)).unwrap();
+        game.handle_action(p2, spawn(0, 7, 7)).unwrap();
+        assert!(matches!(game.handle_action(p1, ProposedAction::EndTurn), Err(GameError::WrongPhase)));
+
+        game.commit_turn();
+        let first = game.current_unit().unwrap();
+        let owner = game.get_unit(first).unwrap().owner;
+        let other = if owner == p1 { p2 } else { p1 };
+
+        assert!(matches!(game.handle_action(other, ProposedAction::EndTurn), Err(GameError::NotYourTurn)));
+        assert_eq!(game.current_unit(), Some(first));
+
+        game.handle_action(owner, ProposedAction::EndTurn).unwrap();
+        let second = game.current_unit().unwrap();
+        assert_ne!(second, first);
+        assert_eq!(game.get_unit(second).unwrap().owner, other);
+        assert!(matches!(game.previous_changes().last(), Some(ResolvedChange::EndTurn)));
+
+        // Replaying the marker changes nothing
+        game.apply_resolution(ResolvedChange::EndTurn);
+        assert_eq!(game.current_unit(), Some(second));
+    }
+
+    #[test]
+    fn test_kill_unit_removes_everywhere() {
+        let mut game = test_game();
+        let p1 = PlayerId::new(1);
+        game.handle_action(p1, spawn(0, 0, 0)).unwrap();
+        game.handle_action(p1, spawn(0, 1, 0)).unwrap();
+        game.queue.reset_from_game(&game.units);
+
+        let dead = game.grid.get_occupancy(Position::new(0, 0)).unwrap();
+        game.kill_unit(dead);
+
+        assert!(game.get_unit(dead).is_none());
+        assert_eq!(game.grid.get_occupancy(Position::new(0, 0)), None);
+        assert!(!game.queue.contains(dead));
+        assert!(matches!(game.curr_turn.changes.last(), Some(ResolvedChange::Death { unit }) if unit.id == dead));
+    }
+
+    #[test]
+    fn test_turn_passes_when_mover_dies() {
+        use crate::core::combat::{AttackDefinition, ReactionDefinition};
+        use crate::core::game::testing::attack;
+        use crate::core::grid::ZoneOfControl;
+
+        // Sentries are slow and kill anyone walking away from them
+        let sentry = UnitDefinition {
+            attacks: vec![AttackDefinition { base_damage: 10, ..attack() }],
+            reactions: vec![ReactionDefinition { trigger: ReactionTrigger::EnemyLeaves, attack: AttackId::new(0) }],
+            base_speed: 1,
+            zone_of_control: ZoneOfControl::Ignore,
+            ..unit_class("Sentry")
+        };
+        let mut game = testing::game()
+            .class(UnitDefinition { base_speed: 5, ..unit_class("Runner") })
+            .class(sentry)
+            .build();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+
+        game.spawn_unit(UnitClassId::new(0), Position::new(2, 2), p1);
+        game.spawn_unit(UnitClassId::new(1), Position::new(3, 2), p2);
+        game.spawn_unit(UnitClassId::new(1), Position::new(6, 6), p1);
+        game.commit_turn();
+        assert_eq!(game.current_unit(), Some(UnitId(0)));
+
+        let path = Path::new(vec![Direction::Left], Position::new(2, 2), Position::new(1, 2));
+        game.handle_action(p1, ProposedAction::Move { path }).unwrap();
+
+        assert!(game.get_unit(UnitId(0)).is_none());
+        assert_eq!(game.current_unit(), Some(UnitId(1)));
+        game.handle_action(p2, ProposedAction::EndTurn).unwrap();
+        assert_eq!(game.current_unit(), Some(UnitId(2)));
+
+        // Killed from outside, its turn can still be ended
+        game.kill_unit(UnitId(2));
+        game.handle_action(p2, ProposedAction::EndTurn).unwrap();
+        assert_ne!(game.current_unit(), Some(UnitId(2)));
+    }
+
+    #[test]
+    fn test_ids_not_reused_after_death() {
+        let mut game = test_game();
+        let p1 = PlayerId::new(1);
+        game.players.get_mut(&p1).unwrap().set_gold(12);
+        game.handle_action(p1, spawn(0, 0, 0)).unwrap();
+        game.handle_action(p1, spawn(0, 1, 0)).unwrap();
+
+        let highest = game.grid.get_occupancy(Position::new(1, 0)).unwrap();
+        game.mark_reacted(highest);
+        game.kill_unit(highest);
+        game.handle_action(p1, spawn(0, 1, 0)).unwrap();
+
+        let new = game.grid.get_occupancy(Position::new(1, 0)).unwrap();
+        assert!(new > highest);
+        assert!(!game.has_reacted(new));
+        assert!(game.get_unit(highest).is_none());
+    }
+
+    #[test]
+    fn test_death_hooks_and_chain() {
+        let mut game = test_game();
+        let p1 = PlayerId::new(1);
+        game.handle_action(p1, spawn(0, 0, 0)).unwrap();
+        game.handle_action(p1, spawn(0, 1, 0)).unwrap();
+
+        // Dying takes every other unit down too
+        game.add_death_hook(Box::new(|game: &mut Game, _: &Unit| {
+            for unit in game.units.values_mut() {
+                unit.health = 0;
+            }
+        }));
+
+        let first = game.grid.get_occupancy(Position::new(0, 0)).unwrap();
+        game.units.get_mut(&first).unwrap().health = 0;
+        game.remove_dead_units();
+
+        assert_eq!(game.units_iter().count(), 0);
+        let deaths = game.curr_turn.changes.iter().filter(|c| matches!(c, ResolvedChange::Death { .. })).count();
+        assert_eq!(deaths, 2);
+    }
+
+    #[test]
+    fn test_surrender_ends_game() {
+        let mut game = test_game();
+        let p1 = PlayerId::new(1);
+        let p2 = PlayerId::new(2);
+        game.handle_action(p2, spawn(0, 7, 7)).unwrap();
+
+        game.handle_action(p2, ProposedAction::Surrender).unwrap();
+
+        assert_eq!(game.units_iter().count(), 0);
+        assert_eq!(game.outcome(), Some(&GameOutcome::Victory { winners: vec![p1] }));
+        assert!(matches!(game.handle_action(p1, spawn(0, 0, 0)), Err(GameError::GameOver)));
+
+        let turn = game.turn_number;
+        game.commit_turn();
+        assert_eq!(game.turn_number, turn);
+    }
+
+    #[test]
+    fn test_last_kill_ends_game() {
+        use crate::core::combat::AttackDefinition;
+        use crate::core::game::testing::attack;
+
+        // Nobody can afford another unit, whoever loses theirs is out
+        let mut game = testing::game()
+            .class(UnitDefinition {
+                attacks: vec![AttackDefinition { base_damage: 10, ..attack() }],
+                cost: 1,
+                ..unit_class("Knight")
+            })
+            .build();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+
+        game.spawn_unit(UnitClassId::new(0), Position::new(2, 2), p1);
+        game.spawn_unit(UnitClassId::new(0), Position::new(3, 2), p2);
+        game.commit_turn();
+
+        game.handle_action(p1, ProposedAction::Attack { target: UnitId(1), attack: AttackId::new(0) }).unwrap();
+
+        assert_eq!(game.outcome(), Some(&GameOutcome::Victory { winners: vec![p1] }));
+        assert!(matches!(game.curr_turn.phase, RoundPhase::GameOver));
+    }
+
+    #[test]
+    fn test_hold_objective() {
+        use crate::core::game::victory::HoldObjective;
+
+        let mut game = test_game();
+        let p1 = PlayerId::new(1);
+        let p2 = PlayerId::new(2);
+        game.add_victory_condition(Box::new(HoldObjective::new(vec![Position::new(0, 0)], 2)));
+
+        game.handle_action(p1, spawn(0, 0, 0)).unwrap();
+        game.handle_action(p2, spawn(0, 7, 7)).unwrap();
+
+        for _ in 0..20 {
+            if game.outcome().is_some() {
+                break;
+            }
+            game.commit_turn();
+        }
+
+        assert_eq!(game.outcome(), Some(&GameOutcome::Victory { winners: vec![p1] }));
+        assert_eq!(game.round_number(), 3);
+        assert!(matches!(game.curr_turn.phase, RoundPhase::GameOver));
+    }
+
+    #[test]
+    fn test_team_shared_victory() {
+        use crate::core::player::TeamId;
+
+        let (a, b, c) = (PlayerId::new(1), PlayerId::new(2), PlayerId::new(3));
+        let mut players = HashMap::new();
+        for (id, team) in [(a, Some(0)), (b, Some(0)), (c, None)] {
+            let mut player = Player::new(format!("{:?}", id));
+            player.set_gold(10);
+            if let Some(team) = team {
+                player = player.with_team(TeamId::new(team));
+            }
+            players.insert(id, player);
+        }
+
+        let mut registry = ClassRegistry::new();
+        registry.register(UnitClassId::new(0), spearman());
+        let mut game = Game::new(players, Grid::new(4, 4), registry);
+        assert!(game.are_allies(a, b));
+        assert!(game.are_enemies(a, c));
+        assert!(game.target_filter_allows(TargetFilter::Ally, a, b));
+        assert!(!game.can_damage(a, b));
+
+        game.handle_action(a, ProposedAction::Surrender).unwrap();
+        assert!(game.outcome().is_none());
+
+        game.handle_action(c, ProposedAction::Surrender).unwrap();
+        assert_eq!(game.outcome(), Some(&GameOutcome::Victory { winners: vec![a, b] }));
+    }
+}
@@ -0,0 +1,166 @@
+//! Which units an attack may target.
+//!
+//! `legal_targets` (UI highlighting) and `validate_attack` (server rules)
+//! both go through `check_target`, so they cannot disagree.
+
+use crate::core::combat::{ActionKey, AttackDefinition, AttackId};
+use crate::core::geom::line::hex_line;
+use crate::core::geom::{shapes, Position};
+use crate::core::player::PlayerId;
+use crate::core::unit::{Unit, UnitId};
+
+use super::state::GameError;
+use super::Game;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    pub unit: UnitId,
+    pub position: Position,
+}
+
+impl Game {
+    /// Every unit `attacker` could hit with `attack` right now
+    pub fn legal_targets(&self, attacker: UnitId, attack: AttackId) -> Vec<Target> {
+        let Some(unit) = self.get_unit(attacker) else { return Vec::new() };
+        let Some(definition) = self.registry().attack(unit.class, attack) else { return Vec::new() };
+        if self.check_usage(unit, ActionKey::Attack(attack)).is_err() {
+            return Vec::new();
+        }
+
+        let mut targets: Vec<Target> = shapes::disk(definition.range.outer_radius as i16)
+            .map(|delta| unit.get_pos().offset(delta))
+            .filter_map(|pos| self.grid().get_occupancy(pos))
+            .filter_map(|id| self.get_unit(id))
+            .filter(|target| self.check_target(unit, definition, target).is_ok())
+            .map(|target| Target { unit: target.id, position: target.get_pos() })
+            .collect();
+
+        targets.sort_by_key(|t| t.unit);
+        targets
+    }
+
+    /// Check a proposed attack by `player` with the unit whose turn it is
+    pub fn validate_attack(&self, player: PlayerId, target: UnitId, attack: AttackId) -> Result<(), GameError> {
+        let attacker = self.current_unit().ok_or(GameError::WrongPhase)?;
+        let unit = self.get_unit(attacker).ok_or(GameError::InvalidUnit)?;
+        if unit.owner != player {
+            return Err(GameError::NotYourTurn);
+        }
+
+        let definition = self.registry()
+            .attack(unit.class, attack)
+            .ok_or(GameError::UnknownAttack)?;
+        self.check_usage(unit, ActionKey::Attack(attack))?;
+        let target = self.get_unit(target).ok_or(GameError::InvalidUnit)?;
+
+        self.check_target(unit, definition, target)
+    }
+
+    pub(super) fn check_target(&self, attacker: &Unit, attack: &AttackDefinition, target: &Unit) -> Result<(), GameError> {
+        if attacker.id == target.id {
+            return Err(GameError::InvalidTarget);
+        }
+        if !self.target_filter_allows(attack.target, attacker.owner, target.owner) {
+            return Err(GameError::InvalidTarget);
+        }
+        if !attack.range.contains(attacker.get_pos().distance(target.get_pos())) {
+            return Err(GameError::OutOfRange);
+        }
+        if attack.needs_line_of_sight && !self.has_line_of_sight(attacker.get_pos(), target.get_pos()) {
+            return Err(GameError::NoLineOfSight);
+        }
+
+        Ok(())
+    }
+
+    /// Sight is blocked by any tile in between that rises above both ends
+    pub fn has_line_of_sight(&self, from: Position, to: Position) -> bool {
+        let grid = self.grid();
+        let (Some(h_from), Some(h_to)) = (grid.get_height(from), grid.get_height(to)) else {
+            return false;
+        };
+        let eye_level = h_from.max(h_to);
+
+        let line = hex_line(from, to);
+        line[1..line.len() - 1]
+            .iter()
+            .all(|&pos| grid.get_height(pos).is_some_and(|h| h <= eye_level))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::combat::AttackRange;
+    use crate::core::game::testing::{self, attack, unit_class};
+    use crate::core::grid::Grid;
+    use crate::core::unit::{UnitClassId, UnitDefinition};
+
+    fn archer_game(grid: Grid) -> Game {
+        testing::game()
+            .class(UnitDefinition {
+                attacks: vec![AttackDefinition {
+                    range: AttackRange { inner_radius: 2, outer_radius: 3 },
+                    needs_line_of_sight: true,
+                    ..attack()
+                }],
+                base_speed: 5,
+                ..unit_class("Archer")
+            })
+            .grid(grid)
+            .build()
+    }
+
+    #[test]
+    fn test_targets_respect_range_and_filter() {
+        let mut game = archer_game(Grid::new(10, 10));
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(5, 5), p1);  // UnitId(0), the archer
+        game.spawn_unit(class, Position::new(6, 5), p2);  // too close
+        game.spawn_unit(class, Position::new(7, 5), p2);  // in range
+        game.spawn_unit(class, Position::new(5, 8), p1);  // ally
+        game.spawn_unit(class, Position::new(9, 5), p2);  // too far
+
+        let targets = game.legal_targets(UnitId(0), AttackId::new(0));
+        assert_eq!(targets, vec![Target { unit: UnitId(2), position: Position::new(7, 5) }]);
+    }
+
+    #[test]
+    fn test_hill_blocks_line_of_sight() {
+        let mut grid = Grid::new(10, 10);
+        grid.set_height(Position::new(3, 2), 3).unwrap();
+        grid.set_height(Position::new(5, 2), 3).unwrap();  // target on the same hill height sees over
+
+        let mut game = archer_game(grid);
+        let class = UnitClassId::new(0);
+        game.spawn_unit(class, Position::new(1, 2), PlayerId::new(1));
+        game.spawn_unit(class, Position::new(4, 2), PlayerId::new(2));
+        game.spawn_unit(class, Position::new(4, 1), PlayerId::new(2));
+
+        assert!(!game.has_line_of_sight(Position::new(1, 2), Position::new(4, 2)));
+        assert!(game.has_line_of_sight(Position::new(2, 2), Position::new(5, 2)));
+
+        let targets = game.legal_targets(UnitId(0), AttackId::new(0));
+        assert_eq!(targets.iter().map(|t| t.unit).collect::<Vec<_>>(), vec![UnitId(2)]);
+    }
+
+    #[test]
+    fn test_validate_attack_matches_targets() {
+        let mut game = archer_game(Grid::new(10, 10));
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+
+        game.spawn_unit(class, Position::new(5, 5), p1);
+        game.spawn_unit(class, Position::new(6, 5), p2);
+        game.spawn_unit(class, Position::new(7, 5), p2);
+        game.commit_turn();  // spawn phase over, fastest then lowest id first
+
+        assert_eq!(game.current_unit(), Some(UnitId(0)));
+        assert!(game.validate_attack(p1, UnitId(2), AttackId::new(0)).is_ok());
+        assert!(matches!(game.validate_attack(p1, UnitId(1), AttackId::new(0)), Err(GameError::OutOfRange)));
+        assert!(matches!(game.validate_attack(p1, UnitId(2), AttackId::new(4)), Err(GameError::UnknownAttack)));
+        assert!(matches!(game.validate_attack(p2, UnitId(2), AttackId::new(0)), Err(GameError::NotYourTurn)));
+    }
+}
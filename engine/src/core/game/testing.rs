@@ -0,0 +1,99 @@
+//! Fixtures shared by the game tests: two players on an 8x8 map and unit
+//! classes built from plain defaults, each test overriding what it's about.
+//!
+//! Override a definition with struct update syntax:
+//! `UnitDefinition { base_speed: 5, ..unit_class("Scout") }`.
+
+use std::collections::HashMap;
+
+use smallvec::SmallVec;
+
+use crate::core::combat::{AoePattern, Armor, AttackDefinition, AttackRange, DamageProfile, TargetFilter, UsageLimit};
+use crate::core::grid::{Grid, ZoneOfControl};
+use crate::core::player::{Player, PlayerId};
+use crate::core::unit::{ClassRegistry, UnitClassId, UnitDefinition};
+
+use super::Game;
+
+/// 10 health, no armor, speed 3, free to spawn, with nothing to do
+pub fn unit_class(name: &str) -> UnitDefinition {
+    UnitDefinition {
+        name: name.to_string(),
+        base_health: 10,
+        armor: Armor::uniform(0.0),
+        evasion: 0.0,
+        attacks: Vec::new(),
+        reactions: Vec::new(),
+        abilities: Vec::new(),
+        actions: Vec::new(),
+        base_speed: 3,
+        zone_of_control: ZoneOfControl::Stop,
+        cost: 0,
+    }
+}
+
+/// A 3 damage slash at an adjacent enemy that always hits and never crits
+pub fn attack() -> AttackDefinition {
+    AttackDefinition {
+        base_damage: 3,
+        damage_type: DamageProfile { pierce: 0, blunt: 0, slash: 1, fire: 0, ice: 0 },
+        armor_penetration: 0.0,
+        range: AttackRange { inner_radius: 1, outer_radius: 1 },
+        aoe: AoePattern::Single,
+        target: TargetFilter::Enemy,
+        needs_line_of_sight: false,
+        hit_chance: 1.0,
+        crit_chance: 0.0,
+        crit_multiplier: 1.5,
+        displacement: None,
+        usage: UsageLimit::default(),
+        effects: SmallVec::new(),
+    }
+}
+
+/// Players 1 and 2 ("one" and "two") with no gold on an empty 8x8 map
+pub fn game() -> GameBuilder {
+    GameBuilder {
+        registry: ClassRegistry::new(),
+        next_class: 0,
+        grid: Grid::new(8, 8),
+        gold: HashMap::new(),
+    }
+}
+
+pub struct GameBuilder {
+    registry: ClassRegistry,
+    next_class: u32,
+    grid: Grid,
+    gold: HashMap<PlayerId, u32>,
+}
+
+impl GameBuilder {
+    /// Registers `definition` under the next class id, from 0 up
+    pub fn class(mut self, definition: UnitDefinition) -> Self {
+        self.registry.register(UnitClassId::new(self.next_class), definition);
+        self.next_class += 1;
+        self
+    }
+
+    pub fn grid(mut self, grid: Grid) -> Self {
+        self.grid = grid;
+        self
+    }
+
+    pub fn gold(mut self, player: PlayerId, gold: u32) -> Self {
+        self.gold.insert(player, gold);
+        self
+    }
+
+    pub fn build(self) -> Game {
+        let mut players = HashMap::new();
+        for (id, name) in [(PlayerId::new(1), "one"), (PlayerId::new(2), "two")] {
+            let mut player = Player::new(name.to_string());
+            player.set_gold(self.gold.get(&id).copied().unwrap_or(0));
+            players.insert(id, player);
+        }
+
+        Game::new(players, self.grid, self.registry)
+    }
+}
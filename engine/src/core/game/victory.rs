@@ -0,0 +1,270 @@
+use crate::core::geom::Position;
+use crate::core::player::PlayerId;
+
+use super::Game;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameOutcome {
+    Victory { winners: Vec<PlayerId> },
+    Draw,
+}
+
+/// A way to end the match. Conditions are checked in order after every
+/// committed turn and every round of deaths, the first one returning an
+/// outcome ends the game.
+/// Winners' allies are added to the victory by the game, conditions don't need to.
+pub trait VictoryCondition {
+    /// Called once per round, right after the round ends and before `check`
+    fn on_round_end(&mut self, _game: &Game) {}
+
+    fn check(&mut self, game: &Game) -> Option<GameOutcome>;
+}
+
+/// Last player or team standing wins, nobody standing is a draw
+pub struct Annihilation;
+
+impl VictoryCondition for Annihilation {
+    fn check(&mut self, game: &Game) -> Option<GameOutcome> {
+        let active = game.active_players();
+        let Some(&first) = active.first() else {
+            return Some(GameOutcome::Draw);
+        };
+
+        active.iter()
+            .all(|&p| game.are_allies(first, p))
+            .then_some(GameOutcome::Victory { winners: active })
+    }
+}
+
+/// A player (or team) whose units stand on every objective tile for `rounds`
+/// consecutive round ends wins
+pub struct HoldObjective {
+    tiles: Vec<Position>,
+    rounds: u32,
+    holder: Option<PlayerId>,
+    streak: u32,
+}
+
+impl HoldObjective {
+    pub fn new(tiles: Vec<Position>, rounds: u32) -> Self {
+        HoldObjective {
+            tiles,
+            rounds,
+            holder: None,
+            streak: 0,
+        }
+    }
+
+    /// A player occupying every objective tile alone or with allies, if any
+    fn current_holder(&self, game: &Game) -> Option<PlayerId> {
+        let mut holder = None;
+
+        for &pos in &self.tiles {
+            let owner = game.grid().get_occupancy(pos)
+                .and_then(|unit_id| game.get_unit(unit_id))
+                .map(|unit| unit.owner)?;
+
+            if holder.is_some_and(|h| !game.are_allies(h, owner)) {
+                return None;
+            }
+            holder.get_or_insert(owner);
+        }
+
+        holder
+    }
+}
+
+impl VictoryCondition for HoldObjective {
+    fn on_round_end(&mut self, game: &Game) {
+        let holder = self.current_holder(game);
+        let same_side = match (holder, self.holder) {
+            (Some(a), Some(b)) => game.are_allies(a, b),
+            _ => false,
+        };
+
+        if same_side {
+            self.streak += 1;
+        } else {
+            self.holder = holder;
+            self.streak = if holder.is_some() { 1 } else { 0 };
+        }
+    }
+
+    fn check(&mut self, game: &Game) -> Option<GameOutcome> {
+        let holder = self.holder?;
+
+        (self.streak >= self.rounds && game.is_active(holder))
+            .then(|| GameOutcome::Victory { winners: vec![holder] })
+    }
+}
+
+/// The given players win if any of them is still active once `rounds` rounds are over
+pub struct SurviveRounds {
+    players: Vec<PlayerId>,
+    rounds: u32,
+}
+
+impl SurviveRounds {
+    pub fn new(players: Vec<PlayerId>, rounds: u32) -> Self {
+        SurviveRounds { players, rounds }
+    }
+}
+
+impl VictoryCondition for SurviveRounds {
+    fn check(&mut self, game: &Game) -> Option<GameOutcome> {
+        if game.round_number() <= self.rounds {
+            return None;
+        }
+
+        let survivors: Vec<PlayerId> = self.players
+            .iter()
+            .copied()
+            .filter(|&p| game.is_active(p))
+            .collect();
+
+        (!survivors.is_empty()).then_some(GameOutcome::Victory { winners: survivors })
+    }
+}
+
+/// First active player to reach `score` wins, several rivals at once is a draw
+pub struct ScoreThreshold {
+    score: u32,
+}
+
+impl ScoreThreshold {
+    pub fn new(score: u32) -> Self {
+        ScoreThreshold { score }
+    }
+}
+
+impl VictoryCondition for ScoreThreshold {
+    fn check(&mut self, game: &Game) -> Option<GameOutcome> {
+        let reached: Vec<PlayerId> = game.active_players()
+            .into_iter()
+            .filter(|&p| game.get_player(p).is_some_and(|player| player.score() >= self.score))
+            .collect();
+
+        let &first = reached.first()?;
+
+        if reached.iter().all(|&p| game.are_allies(first, p)) {
+            Some(GameOutcome::Victory { winners: reached })
+        } else {
+            Some(GameOutcome::Draw)
+        }
+    }
+}
+
+/// Nobody won after `rounds` rounds, the match is a draw
+pub struct RoundLimit {
+    rounds: u32,
+}
+
+impl RoundLimit {
+    pub fn new(rounds: u32) -> Self {
+        RoundLimit { rounds }
+    }
+}
+
+impl VictoryCondition for RoundLimit {
+    fn check(&mut self, game: &Game) -> Option<GameOutcome> {
+        (game.round_number() > self.rounds).then_some(GameOutcome::Draw)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::game::state::ProposedAction;
+    use crate::core::game::testing::{self, unit_class};
+    use crate::core::unit::{UnitClassId, UnitId};
+
+    fn guard_game() -> Game {
+        testing::game().class(unit_class("Guard")).build()
+    }
+
+    /// Ends rounds on an empty board until `round` starts
+    fn skip_to_round(game: &mut Game, round: u32) {
+        while game.round_number() < round {
+            game.commit_turn();
+        }
+    }
+
+    #[test]
+    fn test_hold_objective() {
+        let mut game = guard_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let class = UnitClassId::new(0);
+        let tiles = vec![Position::new(1, 1), Position::new(2, 1)];
+
+        // Every tile is needed, and for `rounds` round ends in a row
+        game.spawn_unit(class, Position::new(1, 1), p1);
+        let mut hold = HoldObjective::new(tiles.clone(), 2);
+        hold.on_round_end(&game);
+        assert_eq!(hold.check(&game), None);
+
+        game.spawn_unit(class, Position::new(2, 1), p1);
+        hold.on_round_end(&game);
+        assert_eq!(hold.check(&game), None);
+        hold.on_round_end(&game);
+        assert_eq!(hold.check(&game), Some(GameOutcome::Victory { winners: vec![p1] }));
+
+        // A contested tile holds nothing, the streak starts over once it's cleared
+        game.kill_unit(UnitId(1));
+        game.spawn_unit(class, Position::new(2, 1), p2);
+        let mut hold = HoldObjective::new(tiles, 1);
+        hold.on_round_end(&game);
+        assert_eq!(hold.check(&game), None);
+
+        game.kill_unit(UnitId(2));
+        game.spawn_unit(class, Position::new(2, 1), p1);
+        hold.on_round_end(&game);
+        assert_eq!(hold.check(&game), Some(GameOutcome::Victory { winners: vec![p1] }));
+    }
+
+    #[test]
+    fn test_survive_rounds() {
+        let mut game = guard_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let mut survive = SurviveRounds::new(vec![p1, p2], 2);
+
+        skip_to_round(&mut game, 2);
+        assert_eq!(survive.check(&game), None);
+
+        skip_to_round(&mut game, 3);
+        assert_eq!(survive.check(&game), Some(GameOutcome::Victory { winners: vec![p1, p2] }));
+
+        // Only those still in the match survive
+        game.handle_action(p2, ProposedAction::Surrender).unwrap();
+        assert_eq!(survive.check(&game), Some(GameOutcome::Victory { winners: vec![p1] }));
+    }
+
+    #[test]
+    fn test_score_threshold() {
+        let mut game = guard_game();
+        let (p1, p2) = (PlayerId::new(1), PlayerId::new(2));
+        let mut threshold = ScoreThreshold::new(5);
+
+        game.award_score(p1, 3);
+        assert_eq!(threshold.check(&game), None);
+
+        game.award_score(p1, 2);
+        assert_eq!(threshold.check(&game), Some(GameOutcome::Victory { winners: vec![p1] }));
+
+        // Rivals reaching it together is a draw
+        game.award_score(p2, 7);
+        assert_eq!(threshold.check(&game), Some(GameOutcome::Draw));
+    }
+
+    #[test]
+    fn test_round_limit() {
+        let mut game = guard_game();
+        let mut limit = RoundLimit::new(2);
+
+        skip_to_round(&mut game, 2);
+        assert_eq!(limit.check(&game), None);
+
+        skip_to_round(&mut game, 3);
+        assert_eq!(limit.check(&game), Some(GameOutcome::Draw));
+    }
+}
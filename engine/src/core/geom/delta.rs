@@ -1,4 +1,13 @@
+/// Mirror lines through the origin, named after the cube coordinate that is
+/// zero along them (q = x, r = y, s = -x - y)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    Q,  // through UpRight and DownLeft
+    R,  // through Right and Left
+    S,  // through DownRight and UpLeft
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Delta {
     dx: i16,
     dy: i16,
@@ -24,6 +33,52 @@ impl Delta {
 
         (self.dx.abs() + self.dy.abs() + s.abs()) / 2
     }
+
+    /// Nearest whole offset to fractional axial coordinates
+    pub fn round(q: f32, r: f32) -> Delta {
+        let s = -q - r;
+
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+        // Fix whichever coordinate rounded furthest so q + r + s stays 0
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+
+        Self::new(rq as i16, rr as i16)
+    }
+
+    pub const fn scale(&self, k: i16) -> Delta {
+        Self::new(self.dx * k, self.dy * k)
+    }
+
+    pub const fn add(&self, other: Delta) -> Delta {
+        Self::new(self.dx + other.dx, self.dy + other.dy)
+    }
+
+    /// Rotated by `steps` 60° turns, in the order of `Direction::ALL`
+    /// (a step turns Right into DownRight), negative turns back
+    pub fn rotated(&self, steps: i8) -> Delta {
+        let (mut q, mut r) = (self.dx, self.dy);
+        for _ in 0..(steps as i16).rem_euclid(6) {
+            // (q, r, s) -> (-s, -q, -r)
+            (q, r) = (q + r, -q);
+        }
+        Self::new(q, r)
+    }
+
+    /// Mirrored across `axis`
+    pub fn reflected(&self, axis: Axis) -> Delta {
+        let (q, r) = (self.dx, self.dy);
+        let s = -q - r;
+        match axis {
+            Axis::Q => Self::new(-q, -s),
+            Axis::R => Self::new(-s, -r),
+            Axis::S => Self::new(-r, -q),
+        }
+    }
 }
 
 
@@ -39,6 +94,29 @@ mod tests {
         assert_eq!(inv.dy, 4);
     }
 
+    #[test]
+    fn test_rotate_and_reflect() {
+        let d = Delta::new(2, -1);
+        assert_eq!(d.rotated(6), d);
+        assert_eq!(d.rotated(3), d.invert());
+        assert_eq!(d.rotated(-1), d.rotated(5));
+        assert_eq!(d.rotated(2).norm(), d.norm());
+
+        for axis in [Axis::Q, Axis::R, Axis::S] {
+            assert_eq!(d.reflected(axis).reflected(axis), d);
+            assert_eq!(d.reflected(axis).norm(), d.norm());
+        }
+        assert_eq!(Delta::new(1, 0).reflected(Axis::R), Delta::new(1, 0));
+        assert_eq!(Delta::new(1, 0).reflected(Axis::Q), Delta::new(-1, 1));
+    }
+
+    #[test]
+    fn test_round() {
+        assert_eq!(Delta::round(0.9, 0.05), Delta::new(1, 0));
+        assert_eq!(Delta::round(1.1, -0.8), Delta::new(1, -1));
+        assert_eq!(Delta::round(-0.3, 0.7), Delta::new(0, 1));
+    }
+
     #[test]
     fn test_norm() {
         assert_eq!(Delta::new(2, 1).norm(), 3);
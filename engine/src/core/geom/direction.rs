@@ -1,4 +1,5 @@
 use super::delta::Delta;
+use super::shapes::NEIGHBORS;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
@@ -11,15 +12,8 @@ pub enum Direction {
 }
 
 impl Direction {
-    pub fn dir_vec(&self) -> Delta {
-        match self {
-            Self::Right     => Delta::new(1, 0),
-            Self::DownRight => Delta::new(1, -1),
-            Self::DownLeft  => Delta::new(0, -1),
-            Self::Left      => Delta::new(-1, 0),
-            Self::UpLeft    => Delta::new(-1, 1),
-            Self::UpRight   => Delta::new(0, 1),
-        }
+    pub const fn dir_vec(&self) -> Delta {
+        NEIGHBORS[self.index()]
     }
 
     pub const ALL: [Direction; 6] = [
@@ -45,6 +39,29 @@ impl Direction {
     pub fn iter() -> impl Iterator<Item = Direction> {
         Self::ALL.into_iter()
     }
+
+    /// Position in `ALL`, neighbors in `ALL` are 60° apart
+    pub const fn index(&self) -> usize {
+        match self {
+            Self::Right     => 0,
+            Self::DownRight => 1,
+            Self::DownLeft  => 2,
+            Self::Left      => 3,
+            Self::UpLeft    => 4,
+            Self::UpRight   => 5,
+        }
+    }
+
+    /// This direction turned `steps` places along `ALL`, negative turns back
+    pub fn rotated(&self, steps: i8) -> Direction {
+        Self::ALL[(self.index() as i8 + steps).rem_euclid(6) as usize]
+    }
+
+    /// Fewest 60° turns from this direction to `other`, 0..=3
+    pub fn turns_to(&self, other: Direction) -> u8 {
+        let turns = (other.index() as i8 - self.index() as i8).rem_euclid(6) as u8;
+        turns.min(6 - turns)
+    }
 }
 
 
@@ -54,6 +71,10 @@ mod tests {
 
     #[test]
     fn test_dir_vec() {
+        for (i, dir) in Direction::iter().enumerate() {
+            assert_eq!(dir.index(), i);
+        }
+
         assert_eq!(Direction::Right.dir_vec(), Delta::new(1, 0));
         assert_eq!(Direction::UpLeft.dir_vec(), Delta::new(-1, 1));
         assert_eq!(Direction::DownLeft.dir_vec(), Delta::new(0, -1));
@@ -64,4 +85,15 @@ mod tests {
         assert_eq!(Direction::Right.invert(), Direction::Left);
         assert_eq!(Direction::UpLeft.invert(), Direction::DownRight);
     }
+
+    #[test]
+    fn test_rotate_and_turns() {
+        for dir in Direction::iter() {
+            assert_eq!(dir.rotated(3), dir.invert());
+            assert_eq!(dir.rotated(-1).rotated(1), dir);
+            assert_eq!(dir.turns_to(dir.invert()), 3);
+        }
+        assert_eq!(Direction::Right.turns_to(Direction::UpRight), 1);
+        assert_eq!(Direction::Right.turns_to(Direction::DownLeft), 2);
+    }
 }
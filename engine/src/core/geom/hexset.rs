@@ -0,0 +1,195 @@
+//! Sets of hex tiles with set algebra.
+//!
+//! A `HexSet` holds either offsets (`Delta`, the default) or map tiles
+//! (`Position`). Tiles are kept sorted, so iterating a set is deterministic
+//! and safe to feed into seeded rolls.
+
+use std::collections::btree_set::{self, BTreeSet};
+
+use super::delta::Delta;
+use super::direction::Direction;
+use super::position::Position;
+
+/// Coordinates a `HexSet` can hold, convertible to and from an offset from (0, 0)
+pub trait HexCoord: Copy + Ord {
+    fn to_delta(self) -> Delta;
+    /// None when the offset has no such coordinate (e.g. a negative position)
+    fn from_delta(delta: Delta) -> Option<Self>;
+}
+
+impl HexCoord for Delta {
+    fn to_delta(self) -> Delta { self }
+    fn from_delta(delta: Delta) -> Option<Self> { Some(delta) }
+}
+
+impl HexCoord for Position {
+    fn to_delta(self) -> Delta { self - Position::new(0, 0) }
+    fn from_delta(delta: Delta) -> Option<Self> { Position::new(0, 0).checked_offset(delta) }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HexSet<T: HexCoord = Delta> {
+    tiles: BTreeSet<T>,
+}
+
+impl<T: HexCoord> Default for HexSet<T> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T: HexCoord> HexSet<T> {
+    pub const fn new() -> Self { Self { tiles: BTreeSet::new() } }
+
+    pub fn len(&self) -> usize { self.tiles.len() }
+    pub fn is_empty(&self) -> bool { self.tiles.is_empty() }
+
+    pub fn insert(&mut self, tile: T) -> bool { self.tiles.insert(tile) }
+    pub fn remove(&mut self, tile: T) -> bool { self.tiles.remove(&tile) }
+    pub fn contains(&self, tile: T) -> bool { self.tiles.contains(&tile) }
+
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        self.tiles.iter().copied()
+    }
+
+    pub fn union(&self, other: &HexSet<T>) -> HexSet<T> {
+        self.tiles.union(&other.tiles).copied().collect()
+    }
+
+    pub fn intersection(&self, other: &HexSet<T>) -> HexSet<T> {
+        self.tiles.intersection(&other.tiles).copied().collect()
+    }
+
+    pub fn difference(&self, other: &HexSet<T>) -> HexSet<T> {
+        self.tiles.difference(&other.tiles).copied().collect()
+    }
+
+    /// Every tile shifted by `by`; tiles with no valid coordinate are dropped
+    pub fn translate(&self, by: Delta) -> HexSet<T> {
+        self.convert(|d| d.add(by))
+    }
+
+    /// Every tile turned by `steps` 60° turns around (0, 0), see `Delta::rotated`.
+    /// To turn a set of positions around a tile, translate it there and back.
+    pub fn rotate(&self, steps: i8) -> HexSet<T> {
+        self.convert(|d| d.rotated(steps))
+    }
+
+    /// Offsets placed around `origin` as map tiles, dropping those off the map's negative edges
+    pub fn placed_at(&self, origin: Position) -> HexSet<Position> {
+        self.iter().filter_map(|t| origin.checked_offset(t.to_delta())).collect()
+    }
+
+    /// Tiles of the set with at least one neighbor outside it
+    pub fn boundary(&self) -> HexSet<T> {
+        self.iter()
+            .filter(|&t| Direction::iter().any(|dir| !neighbor(t, dir).is_some_and(|n| self.contains(n))))
+            .collect()
+    }
+
+    /// Groups of tiles connected through neighbors, each group ordered by its smallest tile
+    pub fn components(&self) -> Vec<HexSet<T>> {
+        let mut left = self.clone();
+        let mut components = Vec::new();
+
+        while let Some(first) = left.tiles.pop_first() {
+            let mut component = HexSet::new();
+            let mut stack = vec![first];
+            component.insert(first);
+
+            while let Some(tile) = stack.pop() {
+                for next in Direction::iter().filter_map(|dir| neighbor(tile, dir)) {
+                    if left.remove(next) {
+                        component.insert(next);
+                        stack.push(next);
+                    }
+                }
+            }
+            components.push(component);
+        }
+
+        components
+    }
+
+    fn convert<U: HexCoord>(&self, f: impl Fn(Delta) -> Delta) -> HexSet<U> {
+        self.iter().filter_map(|t| U::from_delta(f(t.to_delta()))).collect()
+    }
+}
+
+fn neighbor<T: HexCoord>(tile: T, dir: Direction) -> Option<T> {
+    T::from_delta(tile.to_delta().add(dir.dir_vec()))
+}
+
+impl<T: HexCoord> FromIterator<T> for HexSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self { tiles: iter.into_iter().collect() }
+    }
+}
+
+impl<T: HexCoord> Extend<T> for HexSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.tiles.extend(iter);
+    }
+}
+
+impl<'a, T: HexCoord> IntoIterator for &'a HexSet<T> {
+    type Item = T;
+    type IntoIter = std::iter::Copied<btree_set::Iter<'a, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.tiles.iter().copied()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::geom::shapes;
+
+    fn set(tiles: &[(i16, i16)]) -> HexSet {
+        tiles.iter().map(|&(q, r)| Delta::new(q, r)).collect()
+    }
+
+    #[test]
+    fn test_set_algebra() {
+        let a = set(&[(0, 0), (1, 0), (2, 0)]);
+        let b = set(&[(2, 0), (3, 0)]);
+
+        assert_eq!(a.union(&b), set(&[(0, 0), (1, 0), (2, 0), (3, 0)]));
+        assert_eq!(a.intersection(&b), set(&[(2, 0)]));
+        assert_eq!(a.difference(&b), set(&[(0, 0), (1, 0)]));
+        assert!(a.contains(Delta::new(1, 0)) && !a.contains(Delta::new(3, 0)));
+
+        // Duplicates collapse
+        let twice: HexSet = shapes::disk(1).chain(shapes::disk(1)).collect();
+        assert_eq!(twice.len(), 7);
+    }
+
+    #[test]
+    fn test_translate_and_rotate() {
+        let disk: HexSet = shapes::disk(2).collect();
+        assert_eq!(disk.rotate(1), disk);
+        assert_eq!(disk.translate(Delta::new(3, -1)).translate(Delta::new(-3, 1)), disk);
+
+        let line: HexSet = shapes::line(3, Direction::Right).collect();
+        let turned: HexSet = shapes::line(3, Direction::DownLeft).collect();
+        assert_eq!(line.rotate(2), turned);
+
+        // Positions can't go negative, those tiles fall off
+        let placed = disk.placed_at(Position::new(1, 1));
+        assert!(placed.len() < disk.len());
+        assert!(placed.contains(Position::new(3, 1)) && placed.contains(Position::new(0, 0)));
+        assert_eq!(placed.translate(Delta::new(-1, 0)).len(), placed.iter().filter(|p| p.x() > 0).count());
+    }
+
+    #[test]
+    fn test_boundary_and_components() {
+        let disk: HexSet = shapes::disk(2).collect();
+        let ring: HexSet = shapes::ring(2).collect();
+        assert_eq!(disk.boundary(), ring);
+
+        let split = set(&[(0, 0), (1, 0), (0, 1), (5, 5), (5, 6), (9, 0)]);
+        let components = split.components();
+        assert_eq!(components, vec![set(&[(0, 0), (0, 1), (1, 0)]), set(&[(5, 5), (5, 6)]), set(&[(9, 0)])]);
+        assert!(HexSet::<Position>::new().components().is_empty());
+    }
+}
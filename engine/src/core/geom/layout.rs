@@ -0,0 +1,158 @@
+//! Conversions between tiles and screen coordinates, for drawing and mouse picking.
+//!
+//! Screen y grows downward. With pointy-top hexes `Right` points straight
+//! right and `UpRight`/`UpLeft` point up the screen, as their names say.
+//! Flat-top is the same picture turned 30° counterclockwise, so `UpRight`
+//! points straight up and `Right` up and to the right.
+
+use std::f32::consts::{FRAC_PI_3, FRAC_PI_6};
+
+use super::delta::Delta;
+use super::position::Position;
+
+const SQRT_3: f32 = 1.732_050_8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Orientation {
+    #[default]
+    PointyTop,
+    FlatTop,
+}
+
+impl Orientation {
+    /// (q, r) to (x, y) with y pointing up, in units of the hex size
+    fn project(&self, q: f32, r: f32) -> (f32, f32) {
+        match self {
+            Orientation::PointyTop => (SQRT_3 * q + SQRT_3 / 2.0 * r, 1.5 * r),
+            Orientation::FlatTop => (1.5 * q, SQRT_3 / 2.0 * q + SQRT_3 * r),
+        }
+    }
+
+    /// Inverse of `project`
+    fn unproject(&self, x: f32, y: f32) -> (f32, f32) {
+        match self {
+            Orientation::PointyTop => (SQRT_3 / 3.0 * x - y / 3.0, 2.0 / 3.0 * y),
+            Orientation::FlatTop => (2.0 / 3.0 * x, SQRT_3 / 3.0 * y - x / 3.0),
+        }
+    }
+
+    /// Angle of the first corner, counterclockwise from straight right
+    fn corner_angle(&self) -> f32 {
+        match self {
+            Orientation::PointyTop => FRAC_PI_6,
+            Orientation::FlatTop => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    pub orientation: Orientation,
+    pub size: (f32, f32),    // center to corner, horizontally and vertically
+    pub origin: (f32, f32),  // screen point at the center of tile (0, 0)
+}
+
+impl Layout {
+    pub fn new(orientation: Orientation, size: (f32, f32), origin: (f32, f32)) -> Self {
+        Self { orientation, size, origin }
+    }
+
+    /// Screen point at the center of the tile `delta` away from (0, 0)
+    pub fn delta_to_pixel(&self, delta: Delta) -> (f32, f32) {
+        let (x, y) = self.orientation.project(delta.dx() as f32, delta.dy() as f32);
+        (self.origin.0 + x * self.size.0, self.origin.1 - y * self.size.1)
+    }
+
+    pub fn hex_to_pixel(&self, pos: Position) -> (f32, f32) {
+        self.delta_to_pixel(pos - Position::new(0, 0))
+    }
+
+    /// Offset from (0, 0) of the tile containing the screen point
+    pub fn pixel_to_delta(&self, x: f32, y: f32) -> Delta {
+        let plane = ((x - self.origin.0) / self.size.0, (self.origin.1 - y) / self.size.1);
+        let (q, r) = self.orientation.unproject(plane.0, plane.1);
+        Delta::round(q, r)
+    }
+
+    /// Tile containing the screen point, None when it lies past the map's
+    /// zero edges. Whether the tile is on the map is up to the grid.
+    pub fn pixel_to_hex(&self, x: f32, y: f32) -> Option<Position> {
+        Position::new(0, 0).checked_offset(self.pixel_to_delta(x, y))
+    }
+
+    /// Screen points of the six corners of `pos`, counterclockwise
+    pub fn corners(&self, pos: Position) -> [(f32, f32); 6] {
+        let (cx, cy) = self.hex_to_pixel(pos);
+        std::array::from_fn(|i| {
+            let angle = self.orientation.corner_angle() + FRAC_PI_3 * i as f32;
+            (cx + self.size.0 * angle.cos(), cy - self.size.1 * angle.sin())
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::geom::Direction;
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3
+    }
+
+    #[test]
+    fn test_directions_on_screen() {
+        let pointy = Layout::new(Orientation::PointyTop, (1.0, 1.0), (0.0, 0.0));
+        assert!(close(pointy.delta_to_pixel(Direction::Right.dir_vec()), (SQRT_3, 0.0)));
+        assert!(close(pointy.delta_to_pixel(Direction::UpRight.dir_vec()), (SQRT_3 / 2.0, -1.5)));
+        assert!(close(pointy.delta_to_pixel(Direction::DownLeft.dir_vec()), (-SQRT_3 / 2.0, 1.5)));
+
+        let flat = Layout::new(Orientation::FlatTop, (1.0, 1.0), (0.0, 0.0));
+        assert!(close(flat.delta_to_pixel(Direction::UpRight.dir_vec()), (0.0, -SQRT_3)));
+        assert!(close(flat.delta_to_pixel(Direction::DownRight.dir_vec()), (1.5, SQRT_3 / 2.0)));
+
+        // Neighbors are always one hex width apart
+        for layout in [pointy, flat] {
+            for dir in Direction::iter() {
+                let (x, y) = layout.delta_to_pixel(dir.dir_vec());
+                assert!((x.hypot(y) - SQRT_3).abs() < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pixel_round_trip() {
+        for orientation in [Orientation::PointyTop, Orientation::FlatTop] {
+            let layout = Layout::new(orientation, (10.0, 8.0), (40.0, 300.0));
+
+            for x in 0..6 {
+                for y in 0..6 {
+                    let pos = Position::new(x, y);
+                    let (px, py) = layout.hex_to_pixel(pos);
+                    assert_eq!(layout.pixel_to_hex(px, py), Some(pos));
+
+                    // Anywhere well inside the hex picks it
+                    for (dx, dy) in [(6.0, 0.0), (-6.0, 0.0), (0.0, 5.0), (0.0, -5.0), (4.0, 4.0)] {
+                        assert_eq!(layout.pixel_to_hex(px + dx, py + dy), Some(pos), "{:?} {:?}", orientation, pos);
+                    }
+                }
+            }
+
+            // Left of and below (0, 0) there is no position
+            assert_eq!(layout.pixel_to_hex(layout.origin.0 - 30.0, layout.origin.1), None);
+        }
+    }
+
+    #[test]
+    fn test_corners() {
+        let layout = Layout::new(Orientation::PointyTop, (2.0, 2.0), (0.0, 0.0));
+        let (a, b) = (Position::new(1, 1), Position::new(2, 1));
+
+        let center = layout.hex_to_pixel(a);
+        assert!(layout.corners(a).iter().all(|c| ((c.0 - center.0).hypot(c.1 - center.1) - 2.0).abs() < 1e-3));
+
+        // Neighbors share an edge
+        let shared = layout.corners(a).iter().filter(|&&c| layout.corners(b).iter().any(|&d| close(c, d))).count();
+        assert_eq!(shared, 2);
+    }
+}
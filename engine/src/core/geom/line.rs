@@ -0,0 +1,58 @@
+//! Straight lines between hex tiles.
+
+use super::delta::Delta;
+use super::direction::Direction;
+use super::position::Position;
+
+/// Tiles on the straight line from `a` to `b`, both included
+pub fn hex_line(a: Position, b: Position) -> Vec<Position> {
+    let n = a.distance(b);
+    if n == 0 {
+        return vec![a];
+    }
+
+    // Nudge off tile edges so rounding is consistent
+    let (aq, ar) = (a.x() as f32 + 1e-4, a.y() as f32 + 1e-4);
+    let (bq, br) = (b.x() as f32 + 1e-4, b.y() as f32 + 1e-4);
+
+    (0..=n)
+        .map(|i| {
+            let t = i as f32 / n as f32;
+            let tile = Delta::round(aq + (bq - aq) * t, ar + (br - ar) * t);
+
+            Position::new(tile.dx() as usize, tile.dy() as usize)
+        })
+        .collect()
+}
+
+/// Direction of every step along `hex_line(a, b)`
+pub fn line_steps(a: Position, b: Position) -> Vec<Direction> {
+    hex_line(a, b)
+        .windows(2)
+        .filter_map(|w| Direction::iter().find(|d| w[0].offset(d.dir_vec()) == w[1]))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_line() {
+        let line = hex_line(Position::new(0, 0), Position::new(3, 0));
+        assert_eq!(line.len(), 4);
+        assert_eq!(line[3], Position::new(3, 0));
+
+        let line = hex_line(Position::new(0, 4), Position::new(4, 0));
+        assert_eq!(line.len(), 5);
+        for pair in line.windows(2) {
+            assert_eq!(pair[0].distance(pair[1]), 1);
+        }
+
+        // Lines off the six axes still round onto neighboring tiles
+        let (a, b) = (Position::new(1, 1), Position::new(6, 3));
+        assert_eq!(line_steps(a, b).len(), a.distance(b) as usize);
+        assert_eq!(hex_line(a, a), vec![a]);
+    }
+}
@@ -5,8 +5,13 @@ pub mod delta;
 pub mod position;
 pub mod shapes;
 pub mod path;
+pub mod line;
+pub mod hexset;
+pub mod layout;
 
 pub use direction::Direction;
-pub use delta::Delta;
+pub use delta::{Axis, Delta};
 pub use position::Position;
 pub use path::Path;
+pub use hexset::{HexCoord, HexSet};
+pub use layout::{Layout, Orientation};
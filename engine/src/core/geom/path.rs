@@ -19,6 +19,7 @@ impl Path {
 
     pub fn end(&self) -> Position { self.end }
     pub fn start(&self) -> Position { self.start }
+    pub fn last_step(&self) -> Option<Direction> { self.path.last().copied() }
 }
 
 impl<'a> IntoIterator for &'a Path {
@@ -1,6 +1,8 @@
+use std::ops::Sub;
+
 use super::delta::Delta;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Position {
     x: usize,
     y: usize,
@@ -12,12 +14,36 @@ impl Position {
     pub fn x(&self) -> usize { self.x }
     pub fn y(&self) -> usize { self.y }
 
+    /// Shifted by `delta`. Wraps around on negative results, which then fail
+    /// any bounds check; use `checked_offset` to catch them.
     pub fn offset(&self, delta: Delta) -> Self {
         Self {
             x: ((self.x as isize) + (delta.dx() as isize)) as usize,
             y: ((self.y as isize) + (delta.dy() as isize)) as usize,
         }
     }
+
+    /// Shifted by `delta`, None if a coordinate would go negative
+    pub fn checked_offset(&self, delta: Delta) -> Option<Self> {
+        Some(Self {
+            x: self.x.checked_add_signed(delta.dx() as isize)?,
+            y: self.y.checked_add_signed(delta.dy() as isize)?,
+        })
+    }
+
+    /// Number of steps to `other`
+    pub fn distance(&self, other: Position) -> i16 {
+        (other - *self).norm()
+    }
+}
+
+/// The `Delta` leading from `rhs` to `self`
+impl Sub for Position {
+    type Output = Delta;
+
+    fn sub(self, rhs: Position) -> Delta {
+        Delta::new(self.x as i16 - rhs.x as i16, self.y as i16 - rhs.y as i16)
+    }
 }
 
 
@@ -35,4 +61,23 @@ mod tests {
         assert_eq!(new_pos.x, 7);
         assert_eq!(new_pos.y, 2);
     }
+
+    #[test]
+    fn test_checked_offset() {
+        let pos = Position::new(1, 2);
+        assert_eq!(pos.checked_offset(Delta::new(-1, 3)), Some(Position::new(0, 5)));
+        assert_eq!(pos.checked_offset(Delta::new(-2, 0)), None);
+        assert_eq!(pos.checked_offset(Delta::new(0, -3)), None);
+    }
+
+    #[test]
+    fn test_sub_and_distance() {
+        let (a, b) = (Position::new(1, 4), Position::new(4, 2));
+        assert_eq!(b - a, Delta::new(3, -2));
+        assert_eq!(a.offset(b - a), b);
+        assert_eq!(a - b, (b - a).invert());
+        assert_eq!(a.distance(b), 3);
+        assert_eq!(b.distance(a), 3);
+        assert_eq!(a.distance(a), 0);
+    }
 }
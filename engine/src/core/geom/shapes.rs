@@ -1,65 +1,323 @@
-use crate::core::geom::direction;
+//! Shapes as sets of offsets from an origin. Directional shapes are built
+//! pointing Right and turned with `orient`.
+//!
+//! Every shape is a lazy iterator, nothing here allocates; `collect` one
+//! into a `Vec` or a `HexSet` when it has to be kept.
 
-use super::delta::Delta;
+use std::iter::FusedIterator;
+
+use super::delta::{Axis, Delta};
 use super::direction::Direction;
 
+/// Offsets to the six neighbors, in `Direction::ALL` order
+pub const NEIGHBORS: [Delta; 6] = [
+    Delta::new(1, 0),
+    Delta::new(1, -1),
+    Delta::new(0, -1),
+    Delta::new(-1, 0),
+    Delta::new(-1, 1),
+    Delta::new(0, 1),
+];
+
 /// All 6 hex directions
-pub fn neighbors() -> Vec<Delta> {
-    vec![
-        Direction::Right.dir_vec(),
-        Direction::Left.dir_vec(),
-        Direction::UpRight.dir_vec(),
-        Direction::UpLeft.dir_vec(),
-        Direction::DownRight.dir_vec(),
-        Direction::DownLeft.dir_vec(),
-    ]
+pub fn neighbors() -> std::array::IntoIter<Delta, 6> {
+    NEIGHBORS.into_iter()
+}
+
+/// Number of tiles within `radius` of a tile, itself included
+pub const fn disk_len(radius: i16) -> usize {
+    let r = radius as usize;
+    3 * r * (r + 1) + 1
 }
 
 /// Returns all hex offsets within a given radius (filled disk)
 /// Includes center (0,0)
-pub fn disk(radius: i16) -> Vec<Delta> {
-    let mut results = Vec::new();
+pub fn disk(radius: i16) -> Disk {
+    // The first column, q = -radius, runs from r = 0 to r = radius
+    Disk { radius, q: -radius, r: 0, r_end: radius, remaining: disk_len(radius) }
+}
+
+/// Offsets at exactly `radius`, walked around starting from the UpLeft corner
+pub fn ring(radius: i16) -> Ring {
+    Ring {
+        radius,
+        pos: Direction::UpLeft.dir_vec().scale(radius),
+        side: 0,
+        step: 0,
+        remaining: if radius == 0 { 1 } else { 6 * radius as usize },
+    }
+}
+
+/// Every offset within `radius`, ring by ring outward from the center
+pub fn spiral(radius: i16) -> Spiral {
+    Spiral { radius, ring: ring(0) }
+}
+
+/// Every offset turned by `steps` 60° turns, see `Delta::rotated`
+pub fn rotate(shape: impl IntoIterator<Item = Delta>, steps: i8) -> impl Iterator<Item = Delta> {
+    shape.into_iter().map(move |d| d.rotated(steps))
+}
+
+/// Every offset mirrored across `axis`
+pub fn reflect(shape: impl IntoIterator<Item = Delta>, axis: Axis) -> impl Iterator<Item = Delta> {
+    shape.into_iter().map(move |d| d.reflected(axis))
+}
+
+/// A shape pointing Right turned to point toward `dir`
+pub fn orient(shape: impl IntoIterator<Item = Delta>, dir: Direction) -> impl Iterator<Item = Delta> {
+    rotate(shape, dir.index() as i8)
+}
+
+/// The 120° wedge toward `dir`, `length` tiles deep: 3 tiles in the first
+/// row, 5 in the second and so on. Excludes the origin.
+pub fn cone(length: i16, dir: Direction) -> impl Iterator<Item = Delta> + Clone {
+    // Pointing Right, between its two neighboring directions
+    let (side_a, side_b) = (Direction::DownRight.dir_vec(), Direction::UpRight.dir_vec());
+
+    (0..=length)
+        .flat_map(move |a| (0..=length).map(move |b| (a, b)))
+        .filter(|&ab| ab != (0, 0))
+        .map(move |(a, b)| side_a.scale(a).add(side_b.scale(b)).rotated(dir.index() as i8))
+}
+
+/// `length` tiles straight toward `dir`, excluding the origin
+pub fn line(length: i16, dir: Direction) -> impl ExactSizeIterator<Item = Delta> + Clone {
+    (1..=length).map(move |i| dir.dir_vec().scale(i))
+}
+
+/// Iterator returned by `disk`, column by column
+#[derive(Debug, Clone)]
+pub struct Disk {
+    radius: i16,
+    q: i16,
+    r: i16,
+    r_end: i16,  // last r of the current column
+    remaining: usize,
+}
 
-    for q in -radius..=radius {
-        // Since in cubic coords, a disk is |x|, |y|, |z| <= R,
-        // and in axial, z = y - x, we enforce the z constraint here.
-        let r1 = (-radius).max(-q - radius);
-        let r2 = (radius).min(-q + radius);
+impl Iterator for Disk {
+    type Item = Delta;
 
-        for r in r1..=r2 {
-            results.push(Delta::new(q, r));
+    fn next(&mut self) -> Option<Delta> {
+        if self.remaining == 0 {
+            return None;
         }
+        self.remaining -= 1;
+
+        let out = Delta::new(self.q, self.r);
+        if self.r < self.r_end {
+            self.r += 1;
+        } else {
+            // In cube coords a disk is |q|, |r|, |s| <= radius, which bounds r per column
+            self.q += 1;
+            self.r = (-self.radius).max(-self.radius - self.q);
+            self.r_end = self.radius.min(self.radius - self.q);
+        }
+        Some(out)
     }
 
-    results
-}
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
 
-pub fn ring(radius: i16) -> Vec<Delta> {
-    if radius == 0 {
-        return vec![Delta::new(0, 0)];
+    // Plain nested loops, much faster than `next` for sum, for_each and friends
+    fn fold<B, F: FnMut(B, Delta) -> B>(self, init: B, mut f: F) -> B {
+        let mut acc = init;
+        if self.remaining == 0 {
+            return acc;
+        }
+
+        let (mut r, mut r_end) = (self.r, self.r_end);
+        for q in self.q..=self.radius {
+            for r in r..=r_end {
+                acc = f(acc, Delta::new(q, r));
+            }
+            r = (-self.radius).max(-self.radius - q - 1);
+            r_end = self.radius.min(self.radius - q - 1);
+        }
+        acc
     }
+}
 
-    let directions = super::direction::Direction::iter().map(|d| d.dir_vec());;
+impl ExactSizeIterator for Disk {}
+impl FusedIterator for Disk {}
 
-    let mut results = Vec::with_capacity((radius * 6) as usize);
+/// Iterator returned by `ring`
+#[derive(Debug, Clone)]
+pub struct Ring {
+    radius: i16,
+    pos: Delta,
+    side: usize,  // index into `NEIGHBORS` of the side being walked
+    step: i16,    // steps taken along that side
+    remaining: usize,
+}
 
-    // Start at one corner of the ring
-    let mut q = -radius;
-    let mut r = radius;
+impl Iterator for Ring {
+    type Item = Delta;
 
-    // Walk the 6 sides
-    for d in directions {
-        for _ in 0..radius {
-            results.push(Delta::new(q, r));
-            q += d.dx();
-            r += d.dy();
+    fn next(&mut self) -> Option<Delta> {
+        if self.remaining == 0 {
+            return None;
         }
+        self.remaining -= 1;
+
+        let out = self.pos;
+        if self.remaining > 0 {
+            // Walk the 6 sides, `radius` steps along each direction in turn
+            self.pos = self.pos.add(NEIGHBORS[self.side]);
+            self.step += 1;
+            if self.step == self.radius {
+                self.side += 1;
+                self.step = 0;
+            }
+        }
+        Some(out)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
+}
+
+impl ExactSizeIterator for Ring {}
+impl FusedIterator for Ring {}
 
-    results
+/// Iterator returned by `spiral`
+#[derive(Debug, Clone)]
+pub struct Spiral {
+    radius: i16,
+    ring: Ring,
 }
 
-pub fn cone(length: i16) -> Vec<Delta> {
-    return vec![]
+impl Iterator for Spiral {
+    type Item = Delta;
+
+    fn next(&mut self) -> Option<Delta> {
+        loop {
+            if let Some(d) = self.ring.next() {
+                return Some(d);
+            }
+            if self.ring.radius >= self.radius {
+                return None;
+            }
+            self.ring = ring(self.ring.radius + 1);
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.ring.len() + disk_len(self.radius) - disk_len(self.ring.radius);
+        (left, Some(left))
+    }
+
+    fn fold<B, F: FnMut(B, Delta) -> B>(self, init: B, mut f: F) -> B {
+        let first = self.ring.radius + 1;
+        let acc = self.ring.fold(init, &mut f);
+        (first..=self.radius).fold(acc, |acc, radius| ring(radius).fold(acc, &mut f))
+    }
 }
 
+impl ExactSizeIterator for Spiral {}
+impl FusedIterator for Spiral {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(shape: impl IntoIterator<Item = Delta>) -> Vec<Delta> {
+        let mut shape: Vec<Delta> = shape.into_iter().collect();
+        shape.sort();
+        shape
+    }
+
+    #[test]
+    fn test_six_rotations_are_identity() {
+        let shapes: [Vec<Delta>; 4] = [
+            disk(2).collect(),
+            ring(3).collect(),
+            cone(3, Direction::UpLeft).collect(),
+            line(4, Direction::Right).collect(),
+        ];
+        for shape in shapes {
+            let mut turned = shape.clone();
+            for _ in 0..6 {
+                turned = rotate(turned, 1).collect();
+            }
+            assert_eq!(turned, shape);
+        }
+
+        // Symmetric shapes map onto themselves
+        assert_eq!(sorted(rotate(disk(2), 1)), sorted(disk(2)));
+        assert_eq!(sorted(reflect(ring(2), Axis::S)), sorted(ring(2)));
+    }
+
+    #[test]
+    fn test_cone_and_line() {
+        let cone: Vec<Delta> = cone(2, Direction::Right).collect();
+        assert_eq!(cone.len(), 3 + 5);
+        assert!(cone.contains(&Delta::new(2, 0)) && cone.contains(&Delta::new(2, -2)) && cone.contains(&Delta::new(0, 2)));
+        assert!(cone.iter().all(|d| d.norm() <= 2 && d.dx() >= 0));
+
+        for dir in Direction::iter() {
+            assert!(line(3, dir).eq(orient(line(3, Direction::Right), dir)));
+            assert!(super::cone(1, dir).any(|d| d == dir.dir_vec()));
+        }
+
+        // Mirroring across the Right-Left line keeps a Right cone in place
+        assert_eq!(sorted(reflect(cone.clone(), Axis::R)), sorted(cone));
+    }
+
+    #[test]
+    fn test_disk_ring_spiral() {
+        for radius in 0..5 {
+            let disk: Vec<Delta> = disk(radius).collect();
+            assert_eq!(disk.len(), disk_len(radius));
+            assert_eq!(super::disk(radius).len(), disk_len(radius));
+            assert!(disk.iter().all(|d| d.norm() <= radius));
+
+            let ring: Vec<Delta> = ring(radius).collect();
+            assert_eq!(ring.len(), if radius == 0 { 1 } else { 6 * radius as usize });
+            assert!(ring.iter().all(|d| d.norm() == radius));
+            // Consecutive ring tiles are neighbors, all the way around
+            if radius > 0 {
+                assert!(ring.iter().zip(ring.iter().cycle().skip(1)).all(|(a, b)| b.add(a.invert()).norm() == 1));
+            }
+
+            let spiral: Vec<Delta> = spiral(radius).collect();
+            assert_eq!(super::spiral(radius).len(), spiral.len());
+            assert_eq!(sorted(spiral.iter().copied()), sorted(disk));
+            assert!(spiral.windows(2).all(|w| w[0].norm() <= w[1].norm()));
+        }
+
+        assert!(neighbors().eq(Direction::iter().map(|d| d.dir_vec())));
+    }
+
+    #[test]
+    fn test_fold_matches_next() {
+        fn by_next(shape: impl Iterator<Item = Delta>) -> Vec<Delta> {
+            let mut out = Vec::new();
+            for d in shape {
+                out.push(d);
+            }
+            out
+        }
+        fn by_fold(shape: impl Iterator<Item = Delta>) -> Vec<Delta> {
+            let mut out = Vec::new();
+            shape.for_each(|d| out.push(d));
+            out
+        }
+
+        for radius in 0..4 {
+            // Also when picking up halfway through
+            for skip in [0, 1, 4, 9] {
+                let mut disk = disk(radius);
+                let mut spiral = spiral(radius);
+                disk.by_ref().take(skip).for_each(drop);
+                spiral.by_ref().take(skip).for_each(drop);
+
+                assert_eq!(by_fold(disk.clone()), by_next(disk));
+                assert_eq!(by_fold(spiral.clone()), by_next(spiral));
+            }
+        }
+    }
+}
@@ -0,0 +1,416 @@
+//! Seeded procedural map generation.
+//!
+//! The same `MapParams` (seed included) always produce the same map,
+//! which is what daily challenges and replays rely on.
+
+use std::f32::consts::{FRAC_PI_4, PI, TAU};
+
+use super::super::geom::{Delta, Direction, Position};
+use super::super::player::PlayerId;
+use super::super::rng::Rng;
+use super::{Grid, TerrainType};
+
+/// Highest land elevation, reached with roughness 1.0
+const MAX_ELEVATION: f32 = 6.0;
+/// Spacing of the value noise lattice, in tiles
+const NOISE_CELL: usize = 4;
+/// Radius of the flat area around each spawn point
+const SPAWN_RADIUS: i16 = 1;
+/// Elevation of spawn zones and of the corridors carved between them
+const SPAWN_LEVEL: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    None,
+    Rotational,  // 180° around the map center
+    Mirror,      // reflection across the anti-diagonal, needs a square map
+}
+
+#[derive(Debug, Clone)]
+pub struct MapParams {
+    pub seed: u64,
+    pub width: usize,
+    pub height: usize,
+    pub water_ratio: f32,  // fraction of tiles that become water, 0..1
+    pub roughness: f32,    // 0 is flat, 1 is very hilly
+    pub players: u8,
+    pub symmetry: Symmetry,
+}
+
+impl Default for MapParams {
+    fn default() -> Self {
+        MapParams {
+            seed: 0,
+            width: 16,
+            height: 16,
+            water_ratio: 0.2,
+            roughness: 0.5,
+            players: 2,
+            symmetry: Symmetry::Rotational,
+        }
+    }
+}
+
+pub struct GeneratedMap {
+    pub grid: Grid,
+    pub spawns: Vec<Vec<Position>>,  // one spawn zone per player
+}
+
+impl GeneratedMap {
+    /// Hand the spawn zones to the players, in order, and return the finished grid
+    pub fn into_grid(mut self, players: &[PlayerId]) -> Grid {
+        debug_assert_eq!(players.len(), self.spawns.len(), "one player per spawn zone expected");
+
+        for (&player, zone) in players.iter().zip(self.spawns) {
+            self.grid.set_spawn_zone(player, zone).expect("spawn zones are cut from the grid");
+        }
+        self.grid
+    }
+}
+
+#[derive(Debug)]
+pub enum MapGenError {
+    NoPlayers,
+    MapTooSmall,
+    InvalidRatio,
+    MirrorNeedsSquareMap,
+    PlayersNotDivisibleBySymmetry,
+}
+
+impl Symmetry {
+    /// Images of `pos` under the symmetry, identity first
+    fn images(&self, pos: Position, width: usize, height: usize) -> Vec<Position> {
+        let (x, y) = (pos.x(), pos.y());
+
+        match self {
+            Symmetry::None => vec![pos],
+            // Point reflection, (q, r) -> (-q, -r) in axial coords
+            Symmetry::Rotational => vec![pos, Position::new(width - 1 - x, height - 1 - y)],
+            // (q, r) -> (-r, -q) in axial coords
+            Symmetry::Mirror => vec![pos, Position::new(height - 1 - y, width - 1 - x)],
+        }
+    }
+
+    fn order(&self) -> usize {
+        match self {
+            Symmetry::None => 1,
+            Symmetry::Rotational | Symmetry::Mirror => 2,
+        }
+    }
+}
+
+/// Generate a map from `params`.
+/// Every spawn zone is guaranteed to be reachable from every other one.
+pub fn generate(params: &MapParams) -> Result<GeneratedMap, MapGenError> {
+    validate(params)?;
+
+    let (width, height) = (params.width, params.height);
+    let mut rng = Rng::new(params.seed);
+    let mut grid = Grid::new(width, height);
+
+    let noise = symmetrize(&value_noise(&mut rng, params), params);
+    shape_terrain(&mut grid, &noise, params);
+
+    let centers = spawn_centers(params);
+    let spawns: Vec<Vec<Position>> = centers
+        .iter()
+        .map(|&c| spawn_zone(&grid, c))
+        .collect();
+
+    for zone in &spawns {
+        for &pos in zone {
+            clear_tile(&mut grid, pos);
+        }
+    }
+
+    connect_spawns(&mut grid, &centers, params);
+
+    Ok(GeneratedMap { grid, spawns })
+}
+
+fn validate(params: &MapParams) -> Result<(), MapGenError> {
+    if params.players == 0 {
+        return Err(MapGenError::NoPlayers);
+    }
+    if params.width.min(params.height) < 8 {
+        return Err(MapGenError::MapTooSmall);
+    }
+    if !(0.0..=1.0).contains(&params.water_ratio) || !(0.0..=1.0).contains(&params.roughness) {
+        return Err(MapGenError::InvalidRatio);
+    }
+    if params.symmetry == Symmetry::Mirror && params.width != params.height {
+        return Err(MapGenError::MirrorNeedsSquareMap);
+    }
+    if !(params.players as usize).is_multiple_of(params.symmetry.order()) {
+        return Err(MapGenError::PlayersNotDivisibleBySymmetry);
+    }
+    Ok(())
+}
+
+/// Smooth value noise in [0, 1) plus per-tile jitter scaled by roughness
+fn value_noise(rng: &mut Rng, params: &MapParams) -> Vec<f32> {
+    let (width, height) = (params.width, params.height);
+    let lattice_w = width / NOISE_CELL + 2;
+    let lattice_h = height / NOISE_CELL + 2;
+    let lattice: Vec<f32> = (0..lattice_w * lattice_h).map(|_| rng.next_f32()).collect();
+
+    let smoothstep = |t: f32| t * t * (3.0 - 2.0 * t);
+    let jitter = params.roughness * 0.5;
+
+    let mut noise = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let (cx, cy) = (x / NOISE_CELL, y / NOISE_CELL);
+            let tx = smoothstep((x % NOISE_CELL) as f32 / NOISE_CELL as f32);
+            let ty = smoothstep((y % NOISE_CELL) as f32 / NOISE_CELL as f32);
+
+            let at = |i: usize, j: usize| lattice[j * lattice_w + i];
+            let top = at(cx, cy) * (1.0 - tx) + at(cx + 1, cy) * tx;
+            let bottom = at(cx, cy + 1) * (1.0 - tx) + at(cx + 1, cy + 1) * tx;
+            let smooth = top * (1.0 - ty) + bottom * ty;
+
+            noise.push(smooth * (1.0 - jitter) + rng.next_f32() * jitter);
+        }
+    }
+
+    noise
+}
+
+/// Make every tile take the value of the first tile of its symmetry orbit
+fn symmetrize(noise: &[f32], params: &MapParams) -> Vec<f32> {
+    let (width, height) = (params.width, params.height);
+    let idx = |p: Position| p.y() * width + p.x();
+
+    let mut out = noise.to_vec();
+    for y in 0..height {
+        for x in 0..width {
+            let pos = Position::new(x, y);
+            let canonical = params.symmetry
+                .images(pos, width, height)
+                .into_iter()
+                .map(idx)
+                .min()
+                .unwrap();
+            out[idx(pos)] = noise[canonical];
+        }
+    }
+
+    out
+}
+
+/// Lowest tiles become water, the rest are land with elevation from the noise
+fn shape_terrain(grid: &mut Grid, noise: &[f32], params: &MapParams) {
+    let mut sorted = noise.to_vec();
+    sorted.sort_by(f32::total_cmp);
+
+    let water_count = (params.water_ratio * noise.len() as f32).round() as usize;
+    let sea_level = if water_count >= sorted.len() { f32::MAX } else { sorted[water_count] };
+    let levels = (params.roughness * MAX_ELEVATION).round();
+
+    for y in 0..params.height {
+        for x in 0..params.width {
+            let pos = Position::new(x, y);
+            let n = noise[y * params.width + x];
+
+            let placed = if n < sea_level {
+                grid.set_terrain(pos, TerrainType::WaterStill).and_then(|_| grid.set_height(pos, 0))
+            } else {
+                let t = (n - sea_level) / (1.0 - sea_level).max(f32::EPSILON);
+                let elevation = 1.0 + (t * (levels + 1.0)).floor().min(levels);
+                grid.set_height(pos, elevation as u8)
+            };
+            placed.expect("noise covers exactly the grid");
+        }
+    }
+}
+
+/// Spawn points spread on a circle around the map center.
+/// Only the first `players / order` are placed freely, the others are their symmetric images.
+fn spawn_centers(params: &MapParams) -> Vec<Position> {
+    let (width, height) = (params.width, params.height);
+    let players = params.players as usize;
+    let free = players / params.symmetry.order();
+
+    let cx = (width - 1) as f32 / 2.0;
+    let cy = (height - 1) as f32 / 2.0;
+    let radius = 0.35 * width.min(height) as f32;
+    let margin = SPAWN_RADIUS as f32;
+
+    let angle = |k: usize| match params.symmetry {
+        Symmetry::None | Symmetry::Rotational => TAU * k as f32 / players as f32,
+        // Stay on one side of the mirror axis
+        Symmetry::Mirror => -FRAC_PI_4 + PI * (k as f32 + 0.5) / free as f32,
+    };
+
+    let fundamental: Vec<Position> = (0..free)
+        .map(|k| {
+            let x = (cx + radius * angle(k).cos()).round().clamp(margin, width as f32 - 1.0 - margin);
+            let y = (cy + radius * angle(k).sin()).round().clamp(margin, height as f32 - 1.0 - margin);
+            Position::new(x as usize, y as usize)
+        })
+        .collect();
+
+    // Player order: all fundamental spawns, then their images
+    let mut centers = Vec::with_capacity(players);
+    for image in 0..params.symmetry.order() {
+        for &pos in &fundamental {
+            centers.push(params.symmetry.images(pos, width, height)[image]);
+        }
+    }
+
+    centers
+}
+
+fn spawn_zone(grid: &Grid, center: Position) -> Vec<Position> {
+    let mut zone = Vec::new();
+    for dq in -SPAWN_RADIUS..=SPAWN_RADIUS {
+        for dr in (-SPAWN_RADIUS).max(-dq - SPAWN_RADIUS)..=SPAWN_RADIUS.min(-dq + SPAWN_RADIUS) {
+            let pos = center.offset(Delta::new(dq, dr));
+            if grid.in_bounds(pos) {
+                zone.push(pos);
+            }
+        }
+    }
+    zone
+}
+
+/// Carve flat ground corridors from the first spawn to any spawn it cannot reach.
+/// Corridors are carved together with their symmetric images so symmetry is kept.
+fn connect_spawns(grid: &mut Grid, centers: &[Position], params: &MapParams) {
+    let Some(&first) = centers.first() else { return };
+    let mut reached = grid.walkable_region(first);
+
+    for &other in &centers[1..] {
+        if reached.contains(other) {
+            continue;
+        }
+
+        let mut curr = first;
+        while curr != other {
+            curr = step_toward(curr, other);
+            for pos in params.symmetry.images(curr, params.width, params.height) {
+                clear_tile(grid, pos);
+            }
+        }
+
+        reached = grid.walkable_region(first);
+        debug_assert!(reached.contains(other), "corridor carving failed");
+    }
+}
+
+/// Level `pos` into walkable ground at spawn height. Off-map tiles are
+/// skipped, both setters reject them alike.
+fn clear_tile(grid: &mut Grid, pos: Position) {
+    let _ = grid.set_terrain(pos, TerrainType::Ground);
+    let _ = grid.set_height(pos, SPAWN_LEVEL);
+}
+
+/// The neighbor of `from` that is closest to `to`
+fn step_toward(from: Position, to: Position) -> Position {
+    Direction::iter()
+        .map(|d| from.offset(d.dir_vec()))
+        .min_by_key(|&p| p.distance(to))
+        .unwrap()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::grid::pathfinding::find_path;
+
+    fn terrain_and_heights(grid: &Grid) -> Vec<(TerrainType, u8)> {
+        let mut out = Vec::new();
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                let pos = Position::new(x, y);
+                out.push((*grid.get_terrain_type(pos).unwrap(), grid.get_height(pos).unwrap()));
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_same_seed_same_map() {
+        let params = MapParams { seed: 1234, ..MapParams::default() };
+        let a = generate(&params).unwrap();
+        let b = generate(&params).unwrap();
+
+        assert_eq!(terrain_and_heights(&a.grid), terrain_and_heights(&b.grid));
+        assert_eq!(a.spawns, b.spawns);
+    }
+
+    #[test]
+    fn test_spawns_mutually_reachable() {
+        for seed in 0..50 {
+            for symmetry in [Symmetry::None, Symmetry::Rotational, Symmetry::Mirror] {
+                let params = MapParams {
+                    seed,
+                    water_ratio: 0.4,
+                    roughness: 1.0,
+                    players: 4,
+                    symmetry,
+                    ..MapParams::default()
+                };
+                let map = generate(&params).unwrap();
+                assert_eq!(map.spawns.len(), 4);
+
+                for a in &map.spawns {
+                    for b in &map.spawns {
+                        assert!(find_path(&map.grid, a[0], b[0]).is_some(), "seed {} {:?}", seed, symmetry);
+                    }
+                }
+
+                let players: Vec<PlayerId> = (0..4).map(PlayerId::new).collect();
+                assert!(map.into_grid(&players).spawns_connected(), "seed {} {:?}", seed, symmetry);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rotational_symmetry() {
+        let params = MapParams { seed: 99, width: 12, height: 10, ..MapParams::default() };
+        let map = generate(&params).unwrap();
+
+        for y in 0..params.height {
+            for x in 0..params.width {
+                let a = Position::new(x, y);
+                let b = Position::new(params.width - 1 - x, params.height - 1 - y);
+                assert_eq!(map.grid.get_terrain_type(a), map.grid.get_terrain_type(b));
+                assert_eq!(map.grid.get_height(a), map.grid.get_height(b));
+            }
+        }
+    }
+
+    #[test]
+    fn test_mirror_symmetry() {
+        let params = MapParams { seed: 5, symmetry: Symmetry::Mirror, ..MapParams::default() };
+        let map = generate(&params).unwrap();
+        let n = params.width;
+
+        for y in 0..n {
+            for x in 0..n {
+                let a = Position::new(x, y);
+                let b = Position::new(n - 1 - y, n - 1 - x);
+                assert_eq!(map.grid.get_terrain_type(a), map.grid.get_terrain_type(b));
+                assert_eq!(map.grid.get_height(a), map.grid.get_height(b));
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(matches!(
+            generate(&MapParams { players: 0, ..MapParams::default() }),
+            Err(MapGenError::NoPlayers)
+        ));
+        assert!(matches!(
+            generate(&MapParams { players: 3, ..MapParams::default() }),
+            Err(MapGenError::PlayersNotDivisibleBySymmetry)
+        ));
+        assert!(matches!(
+            generate(&MapParams { width: 20, symmetry: Symmetry::Mirror, ..MapParams::default() }),
+            Err(MapGenError::MirrorNeedsSquareMap)
+        ));
+    }
+}
@@ -2,100 +2,306 @@ use std::collections::HashMap;
 
 use crate::core::unit::Unit;
 
-use super::super::geom::{Position};
+use super::super::geom::{Direction, HexSet, Position};
 use super::super::unit::{UnitId};
-use super::TerrainType;
+use super::super::player::PlayerId;
+use super::{MapShape, TerrainType};
+
+/// Highest elevation difference a unit can climb in a single step.
+pub const MAX_CLIMB: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridError {
+    OutOfBounds(Position),
+    NoUnit(Position),  // nothing to move off this tile
+}
+
+/// Result of `Grid::cast_ray`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ray {
+    pub tiles: Vec<Position>,     // tiles passed, the stopping tile last
+    pub hit: Option<Position>,    // tile that stopped the ray, None if it ran out or left the map
+}
 
 #[derive(Clone)]
 pub struct Grid {
-    width: usize,
-    height: usize,
+    shape: MapShape,
 
     terrain: Vec<TerrainType>,
     heightmap: Vec<u8>,
     occupancy: Vec<Option<UnitId>>,
+
+    spawn_zones: HashMap<PlayerId, HexSet<Position>>,
+    income_tiles: HashMap<Position, u32>,  // gold per round for whoever holds the tile
 }
 
 impl Grid {
     /// Convert (x, y) to index in flattened Vec
     #[inline]
-    fn idx(&self, pos: Position) -> usize {
-        debug_assert!(self.in_bounds(pos), "Position out of bounds: {:?}", pos);
-        pos.y() * self.width + pos.x()
+    fn idx(&self, pos: Position) -> Option<usize> {
+        self.shape.index(pos)
+    }
+
+    #[inline]
+    fn checked_idx(&self, pos: Position) -> Result<usize, GridError> {
+        self.idx(pos).ok_or(GridError::OutOfBounds(pos))
     }
 
-    pub fn width(&self) -> usize { self.width }
-    pub fn height(&self) -> usize { self.height }
+    /// Size of the map's bounding box; not every tile in it is on the map
+    /// unless the map is a parallelogram
+    pub fn width(&self) -> usize { self.shape.width() }
+    pub fn height(&self) -> usize { self.shape.height() }
+
+    pub fn shape(&self) -> &MapShape { &self.shape }
 
     #[inline]
-    fn in_bounds(&self, pos: Position) -> bool {
-        pos.x() < self.width && pos.y() < self.height
+    pub fn in_bounds(&self, pos: Position) -> bool {
+        self.shape.contains(pos)
+    }
+
+    /// Every tile of the map, row by row
+    pub fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        self.shape.positions()
     }
     
-    /// Constructor for a new grid
+    /// Constructor for a new grid, a `width × height` parallelogram
     pub fn new(width: usize, height: usize) -> Self {
-        let size = width * height;
+        Self::with_shape(MapShape::parallelogram(width, height))
+    }
+
+    /// A hexagonal map of every tile within `radius` of (radius, radius)
+    pub fn hexagon(radius: usize) -> Self {
+        Self::with_shape(MapShape::hexagon(radius))
+    }
+
+    /// A map that's rectangular on screen, see `MapShape::rectangle`
+    pub fn rectangle(width: usize, height: usize) -> Self {
+        Self::with_shape(MapShape::rectangle(width, height))
+    }
+
+    pub fn with_shape(shape: MapShape) -> Self {
+        let size = shape.len();
 
         Grid {
-            width,
-            height,
+            shape,
             terrain: vec![TerrainType::Ground; size],
             heightmap: vec![0; size],
             occupancy: vec![None; size],
+            spawn_zones: HashMap::new(),
+            income_tiles: HashMap::new(),
         }
     }
 
+    /// In-bounds tiles adjacent to `pos`
+    pub fn neighbors(&self, pos: Position) -> impl Iterator<Item = Position> + '_ {
+        Direction::iter()
+            .filter_map(move |dir| pos.checked_offset(dir.dir_vec()))
+            .filter(|&next| self.in_bounds(next))
+    }
+
     pub fn get_terrain_type(&self, pos: Position) -> Option<&TerrainType> {
-        debug_assert!(self.in_bounds(pos), "get_terrain_type called with out-of-bounds position: {:?}", pos);
-        
-        if self.in_bounds(pos) {
-            Some(&self.terrain[self.idx(pos)])
-        } else {
-            None
-        }
+        self.idx(pos).map(|idx| &self.terrain[idx])
+    }
+    
+    pub fn get_height(&self, pos: Position) -> Option<u8> {
+        self.idx(pos).map(|idx| self.heightmap[idx])
+    }
+
+    /// Whether a unit standing on `from` can step onto the adjacent `to`:
+    /// the tile must be walkable and not too steep a climb or drop.
+    pub fn can_step(&self, from: Position, to: Position) -> bool {
+        let (Some(from), Some(to)) = (self.idx(from), self.idx(to)) else { return false };
+
+        let h_from = self.heightmap[from];
+        let h_to = self.heightmap[to];
+
+        self.terrain[to].is_walkable() && h_from.abs_diff(h_to) <= MAX_CLIMB
     }
     
     pub fn populate_occupancy(&mut self, units: &HashMap<UnitId, Unit>) {
-        self.occupancy.clear();
+        self.occupancy.fill(None);
 
-        for unit  in units {
-            self.set_occupancy(unit.1.get_pos(), Some(*unit.0));
+        // Units off the map have no tile to occupy
+        for (&id, unit) in units {
+            if let Some(idx) = self.idx(unit.get_pos()) {
+                self.occupancy[idx] = Some(id);
+            }
         }
     }
     
     pub fn get_occupancy(&self, pos: Position) -> Option<UnitId> {
-        if !self.in_bounds(pos) { return None; }
-        let idx = self.idx(pos);
-        self.occupancy[idx]
+        self.idx(pos).and_then(|idx| self.occupancy[idx])
     }
 
-    pub fn move_occupancy(&mut self, old_pos: Position, new_pos: Position) {
-        debug_assert!(self.in_bounds(old_pos), "move_occupancy called with out-of-bounds old position: {:?}", old_pos);
-        debug_assert!(self.in_bounds(new_pos), "move_occupancy called with out-of-bounds new position: {:?}", new_pos);
-
-        let unit = self.get_occupancy(old_pos)
-            .expect("move_occupancy called but no unit at old_pos");
+    pub fn move_occupancy(&mut self, old_pos: Position, new_pos: Position) -> Result<(), GridError> {
+        let old = self.checked_idx(old_pos)?;
+        let new = self.checked_idx(new_pos)?;
+        let unit = self.occupancy[old].take().ok_or(GridError::NoUnit(old_pos))?;
 
-        self.set_occupancy(old_pos, None);
-        self.set_occupancy(new_pos, Some(unit));
+        self.occupancy[new] = Some(unit);
+        Ok(())
     }
 
     // should only be called by game
-    pub fn set_occupancy(&mut self, pos: Position, unit: Option<UnitId>) {
-        debug_assert!(self.in_bounds(pos), "set_occupancy called with out-of-bounds position: {:?}", pos);
+    pub fn set_occupancy(&mut self, pos: Position, unit: Option<UnitId>) -> Result<(), GridError> {
+        let idx = self.checked_idx(pos)?;
+        self.occupancy[idx] = unit;
+        Ok(())
+    }
+
+    pub fn set_terrain(&mut self, pos: Position, terrain: TerrainType) -> Result<(), GridError> {
+        let idx = self.checked_idx(pos)?;
+        self.terrain[idx] = terrain;
+        Ok(())
+    }
+
+    pub fn set_height(&mut self, pos: Position, height: u8) -> Result<(), GridError> {
+        let idx = self.checked_idx(pos)?;
+        self.heightmap[idx] = height;
+        Ok(())
+    }
+
+    /// Tiles where `player` may spawn units during the spawn phase
+    pub fn spawn_zone(&self, player: PlayerId) -> &HexSet<Position> {
+        static NO_ZONE: HexSet<Position> = HexSet::new();
+        self.spawn_zones.get(&player).unwrap_or(&NO_ZONE)
+    }
 
-        if self.in_bounds(pos) {
-            let idx = self.idx(pos);
-            self.occupancy[idx] = unit;
+    pub fn set_spawn_zone(&mut self, player: PlayerId, zone: impl IntoIterator<Item = Position>) -> Result<(), GridError> {
+        let zone: HexSet<Position> = zone.into_iter().collect();
+        if let Some(pos) = zone.iter().find(|&p| !self.in_bounds(p)) {
+            return Err(GridError::OutOfBounds(pos));
         }
+
+        self.spawn_zones.insert(player, zone);
+        Ok(())
+    }
+
+    /// Every player's spawn zone, by player id
+    pub fn spawn_zones(&self) -> Vec<(PlayerId, &HexSet<Position>)> {
+        let mut zones: Vec<_> = self.spawn_zones.iter().map(|(&player, zone)| (player, zone)).collect();
+        zones.sort_by_key(|&(player, _)| player);
+        zones
+    }
+
+    pub fn is_spawn_tile(&self, player: PlayerId, pos: Position) -> bool {
+        self.spawn_zone(player).contains(pos)
+    }
+
+    pub fn tile_income(&self, pos: Position) -> u32 {
+        self.income_tiles.get(&pos).copied().unwrap_or(0)
+    }
+
+    pub fn set_tile_income(&mut self, pos: Position, income: u32) -> Result<(), GridError> {
+        self.checked_idx(pos)?;
+
+        if income == 0 {
+            self.income_tiles.remove(&pos);
+        } else {
+            self.income_tiles.insert(pos, income);
+        }
+        Ok(())
     }
 
-    pub fn set_terrain(&mut self, pos: Position, terrain: TerrainType) {
-        debug_assert!(self.in_bounds(pos), "set_occupancy called with out-of-bounds position: {:?}", pos);
-        
-        if self.in_bounds(pos) {
-            let idx = self.idx(pos);
-            self.terrain[idx] = terrain;
+    /// Walks from `from` toward `dir` for at most `range` tiles, stopping at
+    /// the first tile that is occupied or rises above the starting tile
+    pub fn cast_ray(&self, from: Position, dir: Direction, range: u16) -> Ray {
+        let mut ray = Ray { tiles: Vec::new(), hit: None };
+        let Some(eye_level) = self.get_height(from) else { return ray };
+
+        let mut pos = from;
+        for _ in 0..range {
+            let Some(next) = pos.checked_offset(dir.dir_vec()) else { break };
+            let Some(idx) = self.idx(next) else { break };
+            pos = next;
+
+            ray.tiles.push(pos);
+            if self.occupancy[idx].is_some() || self.heightmap[idx] > eye_level {
+                ray.hit = Some(pos);
+                break;
+            }
         }
+
+        ray
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cast_ray() {
+        let mut grid = Grid::new(8, 8);
+        let from = Position::new(1, 2);
+
+        // Runs off the map edge without hitting anything
+        let ray = grid.cast_ray(from, Direction::Left, 5);
+        assert_eq!(ray.tiles, vec![Position::new(0, 2)]);
+        assert_eq!(ray.hit, None);
+
+        let ray = grid.cast_ray(from, Direction::Right, 3);
+        assert_eq!(ray.tiles.len(), 3);
+        assert_eq!(ray.hit, None);
+
+        // Stops on the first unit or wall in the way
+        grid.set_occupancy(Position::new(4, 2), Some(UnitId(7))).unwrap();
+        grid.set_height(Position::new(3, 2), 2).unwrap();
+        let ray = grid.cast_ray(from, Direction::Right, 6);
+        assert_eq!(ray.tiles, vec![Position::new(2, 2), Position::new(3, 2)]);
+        assert_eq!(ray.hit, Some(Position::new(3, 2)));
+
+        grid.set_height(Position::new(3, 2), 0).unwrap();
+        assert_eq!(grid.cast_ray(from, Direction::Right, 6).hit, Some(Position::new(4, 2)));
+    }
+
+    #[test]
+    fn test_bounds_are_checked() {
+        let mut grid = Grid::new(3, 2);
+        let outside = Position::new(3, 0);
+
+        assert_eq!(grid.set_height(outside, 1), Err(GridError::OutOfBounds(outside)));
+        assert_eq!(grid.set_terrain(Position::new(0, 2), TerrainType::Void), Err(GridError::OutOfBounds(Position::new(0, 2))));
+        assert_eq!(grid.get_terrain_type(outside), None);
+        assert!(grid.set_spawn_zone(PlayerId::new(0), vec![Position::new(0, 0), outside]).is_err());
+        assert!(grid.spawn_zone(PlayerId::new(0)).is_empty());
+
+        let unit = Position::new(1, 1);
+        assert_eq!(grid.move_occupancy(unit, outside), Err(GridError::OutOfBounds(outside)));
+        assert_eq!(grid.move_occupancy(unit, Position::new(0, 0)), Err(GridError::NoUnit(unit)));
+        grid.set_occupancy(unit, Some(UnitId(1))).unwrap();
+        grid.move_occupancy(unit, Position::new(0, 0)).unwrap();
+        assert_eq!(grid.get_occupancy(Position::new(0, 0)), Some(UnitId(1)));
+    }
+
+    #[test]
+    fn test_neighbors_clip_to_bounds() {
+        let grid = Grid::new(4, 4);
+
+        assert_eq!(grid.neighbors(Position::new(1, 1)).count(), 6);
+        // (0, 0) only has Right and UpRight on the map
+        let mut corner: Vec<Position> = grid.neighbors(Position::new(0, 0)).collect();
+        corner.sort_by_key(|p| (p.x(), p.y()));
+        assert_eq!(corner, vec![Position::new(0, 1), Position::new(1, 0)]);
+    }
+
+    #[test]
+    fn test_hexagon_grid() {
+        let mut grid = Grid::hexagon(2);
+        assert_eq!(grid.positions().count(), 19);
+
+        // The bounding box corners are cut off
+        let corner = Position::new(0, 0);
+        assert!(!grid.in_bounds(corner));
+        assert_eq!(grid.set_height(corner, 1), Err(GridError::OutOfBounds(corner)));
+        assert_eq!(grid.neighbors(Position::new(2, 2)).count(), 6);
+        assert_eq!(grid.neighbors(Position::new(0, 2)).count(), 3);
+
+        // Rays stop at the hexagon's edge, not the bounding box's
+        let ray = grid.cast_ray(Position::new(2, 2), Direction::Left, 5);
+        assert_eq!(ray.tiles, vec![Position::new(1, 2), Position::new(0, 2)]);
+        grid.set_terrain(Position::new(4, 0), TerrainType::Void).unwrap();
+        assert_eq!(grid.get_terrain_type(Position::new(4, 0)), Some(&TerrainType::Void));
     }
 }
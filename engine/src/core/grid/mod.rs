@@ -1,5 +1,13 @@
 pub mod grid;
+pub mod shape;
 pub mod terrain;
+pub mod pathfinding;
+pub mod generator;
+pub mod regions;
 
-pub use grid::Grid;
+pub use grid::{Grid, GridError, Ray};
+pub use shape::MapShape;
 pub use terrain::TerrainType;
+pub use pathfinding::{MoveRules, ZoneOfControl};
+pub use regions::{Region, RegionMap};
+pub use generator::{GeneratedMap, MapGenError, MapParams, Symmetry};
@@ -0,0 +1,200 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use super::super::geom::{Direction, HexSet, Path, Position};
+use super::Grid;
+
+/// How a unit class is hindered by tiles next to an enemy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ZoneOfControl {
+    /// Entering such a tile ends movement
+    #[default]
+    Stop,
+    /// Entering such a tile costs this much on top of the terrain
+    ExtraCost(u8),
+    /// Walks past enemies freely (e.g. cavalry)
+    Ignore,
+}
+
+/// Movement rules on top of terrain: who's in the way and who projects a zone of control
+#[derive(Debug, Clone, Default)]
+pub struct MoveRules {
+    pub blocked: HexSet<Position>,  // occupied tiles, can't be entered
+    pub zone: HexSet<Position>,  // tiles next to an enemy of the mover
+    pub zone_of_control: ZoneOfControl,
+}
+
+impl MoveRules {
+    /// Cost of stepping onto `to`, terrain included
+    pub fn step_cost(&self, grid: &Grid, to: Position) -> u32 {
+        let terrain = grid.get_terrain_type(to).map_or(u32::MAX, |t| t.get_cost() as u32);
+        match self.zone_of_control {
+            ZoneOfControl::ExtraCost(extra) if self.zone.contains(to) => terrain.saturating_add(extra as u32),
+            _ => terrain,
+        }
+    }
+
+    /// Whether a unit that just entered `pos` must stop there
+    pub fn stops_at(&self, pos: Position) -> bool {
+        self.zone_of_control == ZoneOfControl::Stop && self.zone.contains(pos)
+    }
+
+    fn can_enter(&self, grid: &Grid, from: Position, to: Position) -> bool {
+        grid.can_step(from, to) && !self.blocked.contains(to)
+    }
+}
+
+/// Cheapest path from `start` to `goal` using terrain movement costs.
+/// Only terrain and elevation are considered, occupancy is ignored.
+pub fn find_path(grid: &Grid, start: Position, goal: Position) -> Option<Path> {
+    find_path_with(grid, start, goal, &MoveRules::default())
+}
+
+/// Cheapest path from `start` to `goal` under `rules`
+pub fn find_path_with(grid: &Grid, start: Position, goal: Position, rules: &MoveRules) -> Option<Path> {
+    if !grid.in_bounds(start) || !grid.in_bounds(goal) {
+        return None;
+    }
+
+    let (cost, came_from) = explore(grid, start, rules, u32::MAX, Some(goal));
+    if !cost.contains_key(&goal) {
+        return None;
+    }
+
+    // Walk back from the goal
+    let mut dirs = Vec::new();
+    let mut curr = goal;
+    while curr != start {
+        let (prev, dir) = came_from[&curr];
+        dirs.push(dir);
+        curr = prev;
+    }
+    dirs.reverse();
+
+    Some(Path::new(dirs, start, goal))
+}
+
+/// Every tile reachable from `start` for at most `budget` movement, with its cost.
+/// `start` itself is included at cost 0.
+pub fn reachable(grid: &Grid, start: Position, budget: u32, rules: &MoveRules) -> HashMap<Position, u32> {
+    if !grid.in_bounds(start) {
+        return HashMap::new();
+    }
+
+    explore(grid, start, rules, budget, None).0
+}
+
+/// Dijkstra from `start`, stopping early at `goal` if given
+fn explore(
+    grid: &Grid,
+    start: Position,
+    rules: &MoveRules,
+    budget: u32,
+    goal: Option<Position>,
+) -> (HashMap<Position, u32>, HashMap<Position, (Position, Direction)>) {
+    let mut cost: HashMap<Position, u32> = HashMap::new();
+    let mut came_from: HashMap<Position, (Position, Direction)> = HashMap::new();
+    let mut open = BinaryHeap::new();
+
+    cost.insert(start, 0);
+    open.push(Reverse((0u32, start.y(), start.x())));
+
+    while let Some(Reverse((curr_cost, y, x))) = open.pop() {
+        let curr = Position::new(x, y);
+
+        if Some(curr) == goal {
+            break;
+        }
+        if curr_cost > cost[&curr] {
+            continue;  // stale entry
+        }
+        if curr != start && rules.stops_at(curr) {
+            continue;  // can end here but not go on
+        }
+
+        for dir in Direction::iter() {
+            let next = curr.offset(dir.dir_vec());
+            if !rules.can_enter(grid, curr, next) {
+                continue;
+            }
+
+            let next_cost = curr_cost.saturating_add(rules.step_cost(grid, next));
+            if next_cost > budget {
+                continue;
+            }
+
+            if cost.get(&next).is_none_or(|&c| next_cost < c) {
+                cost.insert(next, next_cost);
+                came_from.insert(next, (curr, dir));
+                open.push(Reverse((next_cost, next.y(), next.x())));
+            }
+        }
+    }
+
+    (cost, came_from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::grid::TerrainType;
+
+    #[test]
+    fn test_straight_path() {
+        let grid = Grid::new(5, 5);
+        let path = find_path(&grid, Position::new(0, 0), Position::new(3, 0)).unwrap();
+        assert_eq!(path.into_iter().count(), 3);
+        assert_eq!(path.end(), Position::new(3, 0));
+    }
+
+    #[test]
+    fn test_path_around_void() {
+        let mut grid = Grid::new(5, 5);
+        for y in 0..4 {
+            grid.set_terrain(Position::new(2, y), TerrainType::Void).unwrap();
+        }
+
+        let path = find_path(&grid, Position::new(0, 0), Position::new(4, 0)).unwrap();
+
+        let mut pos = path.start();
+        for dir in &path {
+            pos = pos.offset(dir.dir_vec());
+            assert_ne!(grid.get_terrain_type(pos), Some(&TerrainType::Void));
+        }
+        assert_eq!(pos, Position::new(4, 0));
+    }
+
+    #[test]
+    fn test_no_path_over_cliff() {
+        let mut grid = Grid::new(3, 3);
+        for y in 0..3 {
+            grid.set_height(Position::new(1, y), 5).unwrap();
+        }
+
+        assert!(find_path(&grid, Position::new(0, 0), Position::new(2, 0)).is_none());
+    }
+
+    #[test]
+    fn test_zone_of_control() {
+        let grid = Grid::new(6, 6);
+        let start = Position::new(0, 0);
+        let mut rules = MoveRules::default();
+        rules.zone.insert(Position::new(1, 0));
+
+        // Stopping: the zone tile is reachable but nothing past it through it
+        let stop = reachable(&grid, start, 2, &rules);
+        assert_eq!(stop.get(&Position::new(1, 0)), Some(&1));
+        assert_eq!(stop.get(&Position::new(2, 0)), None);
+
+        rules.zone_of_control = ZoneOfControl::ExtraCost(2);
+        let slowed = find_path_with(&grid, start, Position::new(2, 0), &rules).unwrap();
+        assert_eq!(slowed.into_iter().count(), 3);  // around is cheaper than through
+        assert_eq!(reachable(&grid, start, 4, &rules)[&Position::new(2, 0)], 3);
+
+        rules.zone_of_control = ZoneOfControl::Ignore;
+        rules.blocked.insert(Position::new(0, 1));
+        let free = reachable(&grid, start, 2, &rules);
+        assert_eq!(free.get(&Position::new(2, 0)), Some(&2));
+        assert_eq!(free.get(&Position::new(0, 1)), None);
+    }
+}
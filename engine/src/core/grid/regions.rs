@@ -0,0 +1,281 @@
+//! Connected regions of a grid: flood fill, components and chokepoints.
+//!
+//! Two walkable tiles are connected when a unit can step between them
+//! (`Grid::can_step`), so cliffs split regions just like void does.
+//! Occupancy is ignored, these describe the map itself.
+
+use std::collections::{BTreeSet, HashMap};
+
+use super::super::geom::{HexSet, Position};
+use super::super::player::PlayerId;
+use super::{Grid, MapShape, TerrainType};
+
+/// A connected area of a single terrain type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub terrain: TerrainType,
+    pub tiles: HexSet<Position>,
+}
+
+/// Every tile of a grid labeled with the terrain region it belongs to
+#[derive(Debug, Clone)]
+pub struct RegionMap {
+    shape: MapShape,
+    labels: Vec<usize>,  // index into `regions`, by `MapShape::index`
+    pub regions: Vec<Region>,
+}
+
+impl RegionMap {
+    pub fn label(&self, pos: Position) -> Option<usize> {
+        self.shape.index(pos).map(|idx| self.labels[idx])
+    }
+
+    pub fn region(&self, pos: Position) -> Option<&Region> {
+        self.label(pos).map(|label| &self.regions[label])
+    }
+}
+
+/// A tile being explored by `Grid::chokepoints`
+struct Visit {
+    tile: Position,
+    parent: Option<Position>,
+    next: Vec<Position>,  // neighbors still to look at
+}
+
+impl Grid {
+    /// Tiles reached from `start` through steps accepted by `connects`, `start` included
+    pub fn flood_fill(&self, start: Position, connects: impl Fn(Position, Position) -> bool) -> HexSet<Position> {
+        let mut filled = HexSet::new();
+        if !self.in_bounds(start) {
+            return filled;
+        }
+
+        filled.insert(start);
+        let mut stack = vec![start];
+        while let Some(tile) = stack.pop() {
+            for next in self.neighbors(tile) {
+                if connects(tile, next) && filled.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+        filled
+    }
+
+    /// Tiles a unit standing on `start` could walk to, empty if `start` isn't walkable
+    pub fn walkable_region(&self, start: Position) -> HexSet<Position> {
+        if !self.is_walkable(start) {
+            return HexSet::new();
+        }
+        self.flood_fill(start, |from, to| self.can_step(from, to))
+    }
+
+    /// Groups of walkable tiles that units can walk between, ordered by their first tile
+    pub fn walkable_components(&self) -> Vec<HexSet<Position>> {
+        let mut seen = HexSet::new();
+        let mut components = Vec::new();
+
+        for pos in self.positions() {
+            if self.is_walkable(pos) && !seen.contains(pos) {
+                let component = self.walkable_region(pos);
+                seen.extend(&component);
+                components.push(component);
+            }
+        }
+        components
+    }
+
+    /// Splits the map into connected areas of identical terrain, heights aside
+    pub fn label_regions(&self) -> RegionMap {
+        let shape = self.shape();
+        let mut labels = vec![usize::MAX; shape.len()];
+        let mut regions = Vec::new();
+
+        for (idx, pos) in shape.positions().enumerate() {
+            if labels[idx] != usize::MAX {
+                continue;
+            }
+
+            let terrain = *self.get_terrain_type(pos).expect("positions are in bounds");
+            let tiles = self.flood_fill(pos, |_, to| self.get_terrain_type(to) == Some(&terrain));
+            for tile in &tiles {
+                labels[shape.index(tile).expect("filled tiles are in bounds")] = regions.len();
+            }
+            regions.push(Region { terrain, tiles });
+        }
+
+        RegionMap { shape: shape.clone(), labels, regions }
+    }
+
+    /// Walkable tiles whose loss would cut their component in two
+    /// (articulation points of the walking graph)
+    pub fn chokepoints(&self) -> HexSet<Position> {
+        let idx = |pos: Position| self.shape().index(pos).expect("walked tiles are in bounds");
+        // Discovery order (0 = not seen yet) and the earliest tile reachable back from a subtree
+        let mut order = vec![0u32; self.shape().len()];
+        let mut low = vec![0u32; self.shape().len()];
+        let mut counter = 0;
+        let mut cuts = HexSet::new();
+
+        for root in self.positions() {
+            if !self.is_walkable(root) || order[idx(root)] != 0 {
+                continue;
+            }
+
+            counter += 1;
+            order[idx(root)] = counter;
+            low[idx(root)] = counter;
+            let mut root_children = 0;
+
+            // Depth first, iterative so large maps can't overflow the stack
+            let mut stack = vec![self.visit(root, None)];
+            while let Some(visit) = stack.last_mut() {
+                let (tile, parent) = (visit.tile, visit.parent);
+
+                if let Some(next) = visit.next.pop() {
+                    if Some(next) == parent {
+                        continue;
+                    }
+                    if order[idx(next)] == 0 {
+                        counter += 1;
+                        order[idx(next)] = counter;
+                        low[idx(next)] = counter;
+                        if tile == root {
+                            root_children += 1;
+                        }
+                        stack.push(self.visit(next, Some(tile)));
+                    } else {
+                        low[idx(tile)] = low[idx(tile)].min(order[idx(next)]);
+                    }
+                    continue;
+                }
+
+                stack.pop();
+                if let Some(parent) = parent {
+                    low[idx(parent)] = low[idx(parent)].min(low[idx(tile)]);
+                    if parent != root && low[idx(tile)] >= order[idx(parent)] {
+                        cuts.insert(parent);
+                    }
+                }
+            }
+
+            if root_children > 1 {
+                cuts.insert(root);
+            }
+        }
+        cuts
+    }
+
+    /// Pairs of players whose spawn zones no unit could walk between
+    pub fn disconnected_spawns(&self) -> Vec<(PlayerId, PlayerId)> {
+        let mut component_of = HashMap::new();
+        for (i, component) in self.walkable_components().iter().enumerate() {
+            component_of.extend(component.iter().map(|pos| (pos, i)));
+        }
+
+        let zones: Vec<(PlayerId, BTreeSet<usize>)> = self.spawn_zones()
+            .into_iter()
+            .map(|(player, zone)| (player, zone.iter().filter_map(|pos| component_of.get(&pos).copied()).collect()))
+            .collect();
+
+        let mut disconnected = Vec::new();
+        for (i, (a, a_components)) in zones.iter().enumerate() {
+            for (b, b_components) in &zones[i + 1..] {
+                if a_components.is_disjoint(b_components) {
+                    disconnected.push((*a, *b));
+                }
+            }
+        }
+        disconnected
+    }
+
+    /// Whether every spawn zone can be walked to from every other one
+    pub fn spawns_connected(&self) -> bool {
+        self.disconnected_spawns().is_empty()
+    }
+
+    fn is_walkable(&self, pos: Position) -> bool {
+        self.get_terrain_type(pos).is_some_and(|t| t.is_walkable())
+    }
+
+    fn visit(&self, tile: Position, parent: Option<Position>) -> Visit {
+        let next = self.neighbors(tile).filter(|&n| self.can_step(tile, n)).collect();
+        Visit { tile, parent, next }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two rooms of 2x3 tiles joined by a one tile wide bridge along y = 1
+    fn bridge_grid() -> Grid {
+        let mut grid = Grid::new(7, 3);
+        for pos in grid.positions().collect::<Vec<_>>() {
+            let room = pos.x() <= 1 || pos.x() >= 5;
+            let bridge = pos.y() == 1;
+            if !room && !bridge {
+                grid.set_terrain(pos, TerrainType::Void).unwrap();
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn test_walkable_components() {
+        let mut grid = bridge_grid();
+        assert_eq!(grid.walkable_components().len(), 1);
+        assert_eq!(grid.walkable_region(Position::new(0, 0)).len(), 15);
+        assert!(grid.walkable_region(Position::new(3, 0)).is_empty());
+
+        // A cliff splits the map as well as void does
+        grid.set_height(Position::new(3, 1), 3).unwrap();
+        let components = grid.walkable_components();
+        assert_eq!(components.len(), 3);
+        assert!(components[0].contains(Position::new(2, 1)) && !components[0].contains(Position::new(4, 1)));
+        assert_eq!(components[2], HexSet::from_iter([Position::new(3, 1)]));
+    }
+
+    #[test]
+    fn test_label_regions() {
+        let mut grid = Grid::new(4, 4);
+        grid.set_terrain(Position::new(1, 1), TerrainType::WaterStill).unwrap();
+        grid.set_terrain(Position::new(2, 1), TerrainType::WaterStill).unwrap();
+        grid.set_terrain(Position::new(3, 3), TerrainType::WaterStill).unwrap();
+        grid.set_height(Position::new(0, 3), 4).unwrap();  // heights don't matter
+
+        let map = grid.label_regions();
+        assert_eq!(map.regions.len(), 3);
+        assert_eq!(map.label(Position::new(1, 1)), map.label(Position::new(2, 1)));
+        assert_ne!(map.label(Position::new(1, 1)), map.label(Position::new(3, 3)));
+        assert_eq!(map.label(Position::new(0, 0)), map.label(Position::new(0, 3)));
+        assert_eq!(map.region(Position::new(0, 0)).unwrap().tiles.len(), 13);
+        assert_eq!(map.label(Position::new(4, 0)), None);
+
+        let hexagon = Grid::hexagon(2).label_regions();
+        assert_eq!(hexagon.regions[0].tiles.len(), 19);
+        assert_eq!(hexagon.label(Position::new(0, 0)), None);
+    }
+
+    #[test]
+    fn test_chokepoints() {
+        let bridge = [Position::new(2, 1), Position::new(3, 1), Position::new(4, 1)];
+        assert_eq!(bridge_grid().chokepoints(), HexSet::from_iter(bridge));
+        assert!(Grid::new(5, 5).chokepoints().is_empty());
+        assert!(Grid::hexagon(3).chokepoints().is_empty());
+    }
+
+    #[test]
+    fn test_spawns_connected() {
+        let (p1, p2, p3) = (PlayerId::new(0), PlayerId::new(1), PlayerId::new(2));
+        let mut grid = bridge_grid();
+        grid.set_spawn_zone(p1, [Position::new(0, 0), Position::new(0, 1)]).unwrap();
+        grid.set_spawn_zone(p2, [Position::new(6, 2)]).unwrap();
+        assert!(grid.spawns_connected());
+
+        grid.set_terrain(Position::new(3, 1), TerrainType::Void).unwrap();
+        grid.set_spawn_zone(p3, [Position::new(1, 2)]).unwrap();
+        assert_eq!(grid.disconnected_spawns(), vec![(p1, p2), (p2, p3)]);
+    }
+}
@@ -0,0 +1,155 @@
+//! Outline of a map and the mapping from its tiles to storage indices.
+//!
+//! Positions are axial, so `x < width && y < height` is a parallelogram
+//! leaning to the right on screen. A hexagon or a screen-aligned rectangle
+//! instead gives every row its own span of `x`. Tiles are stored row by row
+//! with no gaps, so a shape only costs as many slots as it has tiles.
+
+use super::super::geom::Position;
+
+/// Tiles of one row: `x` from `start` to `start + len`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Row {
+    start: usize,
+    len: usize,
+    offset: usize,  // index of the row's first tile
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapShape {
+    width: usize,   // bounding box
+    height: usize,
+    rows: Vec<Row>,
+    len: usize,
+}
+
+impl MapShape {
+    /// Every `x < width` on every row, the plain axial rectangle
+    pub fn parallelogram(width: usize, height: usize) -> Self {
+        Self::from_rows(height, |_| (0, width))
+    }
+
+    /// Every tile within `radius` of the center at (radius, radius)
+    pub fn hexagon(radius: usize) -> Self {
+        // In cube coords |dx|, |dy| and |dx + dy| <= radius around the center
+        Self::from_rows(2 * radius + 1, |y| {
+            let start = radius.saturating_sub(y);
+            let end = (2 * radius).min(3 * radius - y);
+            (start, end - start + 1)
+        })
+    }
+
+    /// `width` tiles on each of `height` rows, stacked straight up the screen
+    /// with every other row shifted half a tile right (pointy-top layout)
+    pub fn rectangle(width: usize, height: usize) -> Self {
+        // Each row up would lean half a tile right, pull it back every second row
+        let lean = height.saturating_sub(1) / 2;
+        Self::from_rows(height, |y| (lean - y / 2, width))
+    }
+
+    /// `span(y)` gives the first `x` and the number of tiles of row `y`
+    fn from_rows(height: usize, span: impl Fn(usize) -> (usize, usize)) -> Self {
+        let mut rows = Vec::with_capacity(height);
+        let mut len = 0;
+        for y in 0..height {
+            let (start, row_len) = span(y);
+            rows.push(Row { start, len: row_len, offset: len });
+            len += row_len;
+        }
+
+        let width = rows.iter().map(|row| row.start + row.len).max().unwrap_or(0);
+        MapShape { width, height, rows, len }
+    }
+
+    /// Width of the bounding box, one past the largest `x`
+    pub fn width(&self) -> usize { self.width }
+    pub fn height(&self) -> usize { self.height }
+
+    /// Number of tiles
+    pub fn len(&self) -> usize { self.len }
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    #[inline]
+    pub fn contains(&self, pos: Position) -> bool {
+        self.rows.get(pos.y()).is_some_and(|row| (row.start..row.start + row.len).contains(&pos.x()))
+    }
+
+    /// Storage index of `pos`, None if it's off the map
+    #[inline]
+    pub fn index(&self, pos: Position) -> Option<usize> {
+        let row = self.rows.get(pos.y())?;
+        (row.start..row.start + row.len).contains(&pos.x()).then(|| row.offset + pos.x() - row.start)
+    }
+
+    /// Inverse of `index`
+    pub fn position(&self, index: usize) -> Option<Position> {
+        if index >= self.len {
+            return None;
+        }
+        let y = self.rows.partition_point(|row| row.offset + row.len <= index);
+        let row = &self.rows[y];
+        Some(Position::new(row.start + index - row.offset, y))
+    }
+
+    /// Every tile in index order, row by row
+    pub fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        self.rows.iter().enumerate().flat_map(|(y, row)| (row.start..row.start + row.len).map(move |x| Position::new(x, y)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::geom::{shapes, Layout, Orientation};
+
+    #[test]
+    fn test_index_round_trip() {
+        for shape in [MapShape::parallelogram(5, 3), MapShape::hexagon(3), MapShape::rectangle(4, 5), MapShape::rectangle(3, 0)] {
+            assert_eq!(shape.positions().count(), shape.len());
+            for (i, pos) in shape.positions().enumerate() {
+                assert!(shape.contains(pos));
+                assert_eq!(shape.index(pos), Some(i));
+                assert_eq!(shape.position(i), Some(pos));
+            }
+            assert_eq!(shape.position(shape.len()), None);
+
+            // Everything else in and around the bounding box is off the map
+            let on_map = (0..shape.width() + 2)
+                .flat_map(|x| (0..shape.height() + 2).map(move |y| Position::new(x, y)))
+                .filter(|&p| shape.contains(p))
+                .count();
+            assert_eq!(on_map, shape.len());
+        }
+    }
+
+    #[test]
+    fn test_hexagon() {
+        let shape = MapShape::hexagon(3);
+        let center = Position::new(3, 3);
+        assert_eq!(shape.len(), shapes::disk_len(3));
+        assert_eq!((shape.width(), shape.height()), (7, 7));
+        assert!(shape.positions().all(|p| p.distance(center) <= 3));
+
+        assert!(shape.contains(Position::new(0, 6)) && shape.contains(Position::new(6, 0)));
+        assert!(!shape.contains(Position::new(0, 0)) && !shape.contains(Position::new(6, 6)));
+        assert!(MapShape::hexagon(0).positions().eq([Position::new(0, 0)]));
+    }
+
+    #[test]
+    fn test_rectangle_lines_up_on_screen() {
+        let shape = MapShape::rectangle(5, 6);
+        let layout = Layout::new(Orientation::PointyTop, (1.0, 1.0), (0.0, 0.0));
+        let left_edges: Vec<f32> = (0..shape.height())
+            .map(|y| shape.positions().filter(|p| p.y() == y).map(|p| layout.hex_to_pixel(p).0).fold(f32::MAX, f32::min))
+            .collect();
+
+        // Even rows start at one edge, odd rows half a tile further right
+        let half = layout.delta_to_pixel(crate::core::geom::Direction::Right.dir_vec()).0 / 2.0;
+        for (y, &edge) in left_edges.iter().enumerate() {
+            let expected = left_edges[0] + if y % 2 == 1 { half } else { 0.0 };
+            assert!((edge - expected).abs() < 1e-3, "row {y} starts at {edge}");
+        }
+        assert_eq!(shape.len(), 30);
+    }
+}
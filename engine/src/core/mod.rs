@@ -5,3 +5,4 @@ pub mod game;
 pub mod geom;
 pub mod player;
 pub mod combat;
+pub mod rng;
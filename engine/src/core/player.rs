@@ -1,8 +1,73 @@
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PlayerId(u32);
 impl PlayerId { pub fn new(val: u32) -> Self { PlayerId(val) }} 
 
+/// Players sharing a team are allies. Players without one play alone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TeamId(u32);
+impl TeamId { pub fn new(val: u32) -> Self { TeamId(val) }}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayerStatus {
+    Active,
+    Eliminated,
+    Surrendered,
+}
+
 #[derive(Clone)]
 pub struct Player {
     name: String,
+    gold: u32,
+    income: u32,  // base gold earned every round, before controlled tiles
+    score: u32,
+    status: PlayerStatus,
+    team: Option<TeamId>,
+}
+
+impl Player {
+    pub fn new(name: String) -> Self {
+        Player {
+            name,
+            gold: 0,
+            income: 0,
+            score: 0,
+            status: PlayerStatus::Active,
+            team: None,
+        }
+    }
+
+    pub fn with_team(mut self, team: TeamId) -> Self {
+        self.team = Some(team);
+        self
+    }
+
+    pub fn name(&self) -> &str { &self.name }
+    pub fn gold(&self) -> u32 { self.gold }
+    pub fn income(&self) -> u32 { self.income }
+    pub fn score(&self) -> u32 { self.score }
+    pub fn status(&self) -> PlayerStatus { self.status }
+    pub fn is_active(&self) -> bool { self.status == PlayerStatus::Active }
+    pub fn team(&self) -> Option<TeamId> { self.team }
+
+    pub fn set_gold(&mut self, gold: u32) { self.gold = gold; }
+    pub fn set_income(&mut self, income: u32) { self.income = income; }
+    pub fn set_status(&mut self, status: PlayerStatus) { self.status = status; }
+    pub fn add_score(&mut self, points: u32) { self.score = self.score.saturating_add(points); }
+
+    pub fn can_afford(&self, cost: u32) -> bool {
+        self.gold >= cost
+    }
+
+    /// Remove `cost` gold, failing without change if the player can't afford it
+    pub fn spend(&mut self, cost: u32) -> bool {
+        if !self.can_afford(cost) {
+            return false;
+        }
+        self.gold -= cost;
+        true
+    }
+
+    pub fn earn(&mut self, amount: u32) {
+        self.gold = self.gold.saturating_add(amount);
+    }
 }
@@ -32,6 +32,16 @@ impl UnitQueue {
         self.queue.pop_front()
     }
 
+    /// Pop the next unit that is still in `units`, dropping any that vanished
+    pub fn next_alive(&mut self, units: &HashMap<UnitId, Unit>) -> Option<UnitId> {
+        while let Some(unit_id) = self.queue.pop_front() {
+            if units.contains_key(&unit_id) {
+                return Some(unit_id);
+            }
+        }
+        None
+    }
+
     /// Peek at the next unit without removing it
     pub fn peek(&self) -> Option<UnitId> {
         self.queue.front().copied()
@@ -42,9 +52,30 @@ impl UnitQueue {
         self.queue.push_back(unit_id);
     }
 
-    /// Recompute the queue ordering from the remaining units using their current speed
+    /// Remove a unit wherever it is in the queue (e.g., on death)
+    pub fn remove_unit(&mut self, unit_id: UnitId) {
+        self.queue.retain(|&id| id != unit_id);
+    }
+
+    pub fn contains(&self, unit_id: UnitId) -> bool {
+        self.queue.contains(&unit_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Recompute the queue ordering from the remaining units using their current speed.
+    /// Units no longer present in `units` are dropped.
     pub fn recompute_from_units(&mut self, units: &HashMap<UnitId, Unit>) {
-        let mut remaining: Vec<UnitId> = self.queue.iter().copied().collect();
+        let mut remaining: Vec<UnitId> = self.queue.iter()
+            .copied()
+            .filter(|id| units.contains_key(id))
+            .collect();
         remaining.sort_unstable_by(|a, b| {
             let a_speed = units.get(a).unwrap().speed();
             let b_speed = units.get(b).unwrap().speed();
@@ -65,3 +96,47 @@ impl UnitQueue {
         self.queue = VecDeque::from(all_units);
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::geom::Position;
+    use crate::core::player::PlayerId;
+    use crate::core::unit::UnitClassId;
+
+    fn units(n: u32) -> HashMap<UnitId, Unit> {
+        (0..n)
+            .map(|i| {
+                let id = UnitId(i);
+                (id, Unit::new(UnitClassId::new(0), PlayerId::new(0), Position::new(i as usize, 0), id))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_recompute_drops_missing_units() {
+        let mut units = units(3);
+        let mut queue = UnitQueue::new(&[]);
+        queue.reset_from_game(&units);
+
+        units.remove(&UnitId(1));
+        queue.recompute_from_units(&units);
+
+        assert_eq!(queue.len(), 2);
+        assert!(!queue.contains(UnitId(1)));
+    }
+
+    #[test]
+    fn test_next_alive_skips_vanished() {
+        let mut units = units(3);
+        let mut queue = UnitQueue::new(&[]);
+        queue.reset_from_game(&units);
+
+        units.remove(&UnitId(0));
+        assert_eq!(queue.next_alive(&units), Some(UnitId(1)));
+
+        queue.remove_unit(UnitId(2));
+        assert_eq!(queue.next_alive(&units), None);
+    }
+}
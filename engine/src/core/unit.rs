@@ -1,8 +1,12 @@
+use std::collections::HashMap;
+
 use tokio::io::unix::AsyncFdTryNewError;
 
 use crate::core::game::state::ProposedAction;
+use crate::core::combat::{AbilityDefinition, Armor, AbilityId, ActionKey, AttackDefinition, AttackId, Cooldowns, Effect, ReactionDefinition, UsageLimit};
 
-use super::geom::Position;
+use super::geom::{Direction, Position};
+use super::grid::ZoneOfControl;
 use super::player::PlayerId;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
@@ -22,11 +26,51 @@ impl UnitId {
 pub struct UnitDefinition {
     pub name: String,
     pub base_health: i32,
-    pub defense: f32,
-    //pub attacks: Vec<AttackDefinition>,
+    pub armor: Armor,
+    pub evasion: f32,  // subtracted from incoming hit chances
+    pub attacks: Vec<AttackDefinition>,  // indexed by AttackId
+    pub reactions: Vec<ReactionDefinition>,  // first usable one per trigger is taken
     pub actions: Vec<ActionPoint>,
-    // pub abilities: Vec<Ability>,
+    pub abilities: Vec<AbilityDefinition>,  // indexed by AbilityId
     pub base_speed: u8,
+    pub zone_of_control: ZoneOfControl,  // how enemy zones slow this class down
+    pub cost: u32,  // gold paid to spawn one
+}
+
+/// Unit classes available in a match, loaded at runtime
+#[derive(Default)]
+pub struct ClassRegistry {
+    pub classes: HashMap<UnitClassId, UnitDefinition>,
+}
+
+impl ClassRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, id: UnitClassId, definition: UnitDefinition) {
+        self.classes.insert(id, definition);
+    }
+
+    pub fn get(&self, id: UnitClassId) -> Option<&UnitDefinition> {
+        self.classes.get(&id)
+    }
+
+    pub fn attack(&self, id: UnitClassId, attack: AttackId) -> Option<&AttackDefinition> {
+        self.get(id)?.attacks.get(attack.index())
+    }
+
+    pub fn ability(&self, id: UnitClassId, ability: AbilityId) -> Option<&AbilityDefinition> {
+        self.get(id)?.abilities.get(ability.index())
+    }
+
+    /// Usage limit of an attack or ability, if the class has it
+    pub fn usage(&self, id: UnitClassId, key: ActionKey) -> Option<&UsageLimit> {
+        match key {
+            ActionKey::Attack(attack) => self.attack(id, attack).map(|a| &a.usage),
+            ActionKey::Ability(ability) => self.ability(id, ability).map(|a| &a.usage),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -35,9 +79,12 @@ pub struct Unit {
     pub owner: PlayerId,
     pub class: UnitClassId,
     pub health: u32,
+    pub shield: u32,  // soaks damage before health, gone at the end of the round
     pub actions: Vec<ActionPoint>,
     pub position: Position,
-    //pub effects: Vec<Effect>,
+    pub facing: Direction,  // set by the last step moved and by turning
+    pub effects: Vec<Effect>,
+    pub cooldowns: Cooldowns,
 }
 
 impl Unit {
@@ -47,8 +94,12 @@ impl Unit {
             owner: owner,
             class: class,
             health: u32::default(),  // This needs to be changed
+            shield: 0,
             actions: Vec::new(),
             position: pos,
+            facing: Direction::Right,
+            effects: Vec::new(),
+            cooldowns: Cooldowns::new(),
         }
     }
 
@@ -1,46 +1,41 @@
 use crate::core::geom::delta::{Delta};
-use std::cmp::{min, max};
+use crate::core::geom::layout::{Layout, Orientation};
 
 pub fn render_deltas(deltas: &[Delta]) {
-    use std::cmp::{min, max};
-
     if deltas.is_empty() {
         println!("No deltas to render.");
         return;
     }
 
-    // Map axial (dx, dy) to screen coordinates
-    let screen_coords: Vec<(i16, i16)> = deltas
+    // Terminal cells are about twice as tall as wide, so a hex step is
+    // two columns across and one row up, the same picture a pointy-top
+    // layout draws
+    let layout = Layout::new(Orientation::PointyTop, (2.0 / 3f32.sqrt(), 2.0 / 3.0), (0.0, 0.0));
+    let cells: Vec<(i32, i32)> = deltas
         .iter()
-        .map(|d| (d.dx() + d.dy(), d.dx()))
+        .map(|&d| {
+            let (x, y) = layout.delta_to_pixel(d);
+            (x.round() as i32, y.round() as i32)
+        })
         .collect();
 
     // Determine bounds
-    let min_col = screen_coords.iter().map(|(c, _)| *c).min().unwrap();
-    let max_col = screen_coords.iter().map(|(c, _)| *c).max().unwrap();
-    let min_row = screen_coords.iter().map(|(_, r)| *r).min().unwrap();
-    let max_row = screen_coords.iter().map(|(_, r)| *r).max().unwrap();
-
-    for row in (min_row..=max_row).rev() {
-        // print spaces to stagger the row (row number spaces)
-        let indent = max_row - row;
-
-        for i in 0..indent {
-            let is_dot = (i + indent) % 2 == 0;
-            print!("{}", if is_dot { '·' } else { ' ' });
-        }
+    let min_col = cells.iter().map(|(c, _)| *c).min().unwrap();
+    let max_col = cells.iter().map(|(c, _)| *c).max().unwrap();
+    let min_row = cells.iter().map(|(_, r)| *r).min().unwrap();
+    let max_row = cells.iter().map(|(_, r)| *r).max().unwrap();
 
+    for row in min_row..=max_row {
         for col in min_col..=max_col {
-            if screen_coords.iter().any(|(c, r)| *c == col && *r == row) {
-                print!("@ ");
+            // Hex centers sit where column and row have the same parity
+            let c = if cells.contains(&(col, row)) {
+                '@'
+            } else if (col + row).rem_euclid(2) == 0 {
+                '·'
             } else {
-                print!("· ");
-            }
-        }
-
-        for i in 0..row {
-            let is_dot = i % 2 == 0;
-            print!("{}", if is_dot { '·' } else { ' ' });
+                ' '
+            };
+            print!("{}", c);
         }
 
         println!();